@@ -11,6 +11,10 @@ pub enum ArgType {
     Boolean,
     Number,
     Command,
+    Strategy,
+    Encoding,
+    Notation,
+    Eta,
 }
 
 use completion::{self, CompleterProvider, Completers};
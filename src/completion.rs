@@ -47,7 +47,7 @@ pub mod completers {
     use rustyline::completion::{extract_word, Completer};
     use lambda_rust::runtime::{Environment, SymbolTable};
     use std::cmp::min;
-    use std::sync::{Arc, Weak, Mutex};
+    use std::sync::{Arc, Weak, Mutex, PoisonError};
     use super::WHITESPACE;
 
     pub struct BoolCompleter;
@@ -69,6 +69,28 @@ pub mod completers {
         }
     }
 
+    pub struct ChoiceCompleter(Vec<&'static str>);
+
+    impl ChoiceCompleter {
+        pub fn new<I: IntoIterator<Item = &'static str>>(choices: I) -> Self {
+            ChoiceCompleter(choices.into_iter().collect())
+        }
+    }
+
+    impl Completer for ChoiceCompleter {
+        fn complete(&self, line: &str, pos: usize) -> rustyline::Result<(usize, Vec<String>)> {
+            let (mut word_start, word) = extract_word(line, pos, None, &WHITESPACE);
+            let matches: Vec<String> = self.0.iter()
+                .filter(|choice| choice.starts_with(word))
+                .map(|choice| choice.to_string())
+                .collect();
+            if matches.is_empty() {
+                word_start = 0;
+            }
+            Ok((word_start, matches))
+        }
+    }
+
     pub struct SymbolTableAdapter<T: SymbolTable>(Weak<Mutex<Environment<T>>>);
 
     impl<T: SymbolTable> SymbolTableAdapter<T> {
@@ -85,7 +107,7 @@ pub mod completers {
                     let (word_start, word) = extract_word(line, pos, None, &WHITESPACE);
                     let prefix = &line[word_start..min(pos, word_start + word.len())];
                     let mut candidates: Vec<_> = {
-                        let lock = runtime.lock().unwrap();
+                        let lock = runtime.lock().unwrap_or_else(PoisonError::into_inner);
                         let symbols = lock.symbol_table().symbols();
                         let candidates = symbols.filter(|s| s.starts_with(prefix)).cloned().collect();
                         candidates
@@ -0,0 +1,249 @@
+//! Readable forms of encoded data in normal forms.
+
+use ::encoding;
+use ::lambda::{Name, Term};
+use ::pretty::PrettyPrinter;
+
+use std::fmt;
+
+pub trait Decoder: Send {
+    /// The name of the encoding, shown next to decoded values.
+    fn name(&self) -> &str;
+
+    /// Reads `term` back as text, or returns `None` if it is not in this
+    /// encoding. Components such as list elements are decoded through
+    /// `decoders`, which shows them with `printer` if they can not be
+    /// decoded.
+    fn decode(&self, term: &Term, decoders: &Decoders, printer: &PrettyPrinter) -> Option<String>;
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Decoded {
+    pub value: String,
+    pub encoding: String,
+}
+
+impl fmt::Display for Decoded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} [{}]", self.value, self.encoding)
+    }
+}
+
+/// An ordered set of decoders. A term is decoded by every decoder that
+/// recognises it, since some terms have several readings: `0` and `false`
+/// are the same term.
+pub struct Decoders {
+    decoders: Vec<Box<dyn Decoder>>,
+}
+
+impl Decoders {
+    /// Creates a registry without any decoders.
+    pub fn empty() -> Decoders {
+        Decoders { decoders: vec![] }
+    }
+
+    /// Registers `decoder` in front of all present ones, so the readings of
+    /// custom decoders come before those of the built-in ones.
+    pub fn add(&mut self, decoder: Box<dyn Decoder>) {
+        self.decoders.insert(0, decoder);
+    }
+
+    /// Reads `term` back with all decoders recognising it, joining their
+    /// values and encoding names with ` / `.
+    pub fn decode(&self, term: &Term, printer: &PrettyPrinter) -> Option<Decoded> {
+        let (values, encodings): (Vec<_>, Vec<_>) = self.decoders.iter()
+            .filter_map(|decoder| decoder.decode(term, self, printer).map(|value| (value, decoder.name())))
+            .unzip();
+
+        if values.is_empty() {
+            None
+        } else {
+            Some(Decoded { value: values.join(" / "), encoding: encodings.join(" / ") })
+        }
+    }
+
+    /// The value read by the first decoder recognising `term`, or the term
+    /// itself as `printer` renders it if there is none.
+    pub fn decode_or_show(&self, term: &Term, printer: &PrettyPrinter) -> String {
+        self.decoders.iter()
+            .filter_map(|decoder| decoder.decode(term, self, printer))
+            .next()
+            .unwrap_or_else(|| printer.render(term))
+    }
+}
+
+impl Default for Decoders {
+    /// The built-in decoders for strings, Church numerals, booleans, pairs
+    /// and lists, in this order.
+    fn default() -> Decoders {
+        Decoders {
+            decoders: vec![
+                Box::new(StringDecoder),
+                Box::new(NumeralDecoder),
+                Box::new(BooleanDecoder),
+                Box::new(PairDecoder),
+                Box::new(ListDecoder),
+            ],
+        }
+    }
+}
+
+pub struct StringDecoder;
+
+impl Decoder for StringDecoder {
+    fn name(&self) -> &str {
+        "string"
+    }
+
+    fn decode(&self, term: &Term, _: &Decoders, _: &PrettyPrinter) -> Option<String> {
+        encoding::decode_string(term).map(|text| format!("{:?}", text))
+    }
+}
+
+pub struct NumeralDecoder;
+
+impl Decoder for NumeralDecoder {
+    fn name(&self) -> &str {
+        "Church numeral"
+    }
+
+    fn decode(&self, term: &Term, _: &Decoders, _: &PrettyPrinter) -> Option<String> {
+        encoding::decode_numeral(term).map(|n| n.to_string())
+    }
+}
+
+/// Decodes `T` (`λx.λy.x`) and `F` (`λx.λy.y`) from the prelude.
+pub struct BooleanDecoder;
+
+impl Decoder for BooleanDecoder {
+    fn name(&self) -> &str {
+        "boolean"
+    }
+
+    fn decode(&self, term: &Term, _: &Decoders, _: &PrettyPrinter) -> Option<String> {
+        let body = match *term {
+            Term::Lambda { ref body, .. } => match **body {
+                Term::Lambda { ref body, .. } => body,
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        match **body {
+            Term::Variable { name: Name::Bound { depth: 2 } } => Some("true".into()),
+            Term::Variable { name: Name::Bound { depth: 1 } } => Some("false".into()),
+            _ => None,
+        }
+    }
+}
+
+/// Decodes pairs `λf.f a b` as built by `pair` from the prelude.
+pub struct PairDecoder;
+
+impl Decoder for PairDecoder {
+    fn name(&self) -> &str {
+        "pair"
+    }
+
+    fn decode(&self, term: &Term, decoders: &Decoders, printer: &PrettyPrinter) -> Option<String> {
+        match encoding::decode_tuple(term) {
+            Some(ref elements) if elements.len() == 2 => {
+                let show = |element| decoders.decode_or_show(element, printer);
+                Some(format!("<{}, {}>", show(&elements[0]), show(&elements[1])))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Decodes non-empty right-fold lists; the empty list is also zero. Lists
+/// of numerals that read as text are left to `StringDecoder`.
+pub struct ListDecoder;
+
+impl Decoder for ListDecoder {
+    fn name(&self) -> &str {
+        "list"
+    }
+
+    fn decode(&self, term: &Term, decoders: &Decoders, printer: &PrettyPrinter) -> Option<String> {
+        match encoding::decode_list(term) {
+            Some(ref elements) if !elements.is_empty() && encoding::decode_string(term).is_none() => {
+                let elements: Vec<_> = elements.iter().map(|e| decoders.decode_or_show(e, printer)).collect();
+                Some(format!("[{}]", elements.join(", ")))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ::lexer::Token;
+    use ::parser::parse;
+    use ::runtime::Statement;
+
+    fn parse_term(input: &str) -> Term {
+        match parse(&Token::parse_all(input).unwrap()) {
+            Ok(Statement::Expression(term)) => term,
+            result => panic!("{:?}", result),
+        }
+    }
+
+    fn decode(input: &str) -> Option<String> {
+        Decoders::default().decode(&parse_term(input), &PrettyPrinter::default()).map(|decoded| decoded.to_string())
+    }
+
+    #[test]
+    fn test_builtin_decoders() {
+        assert_eq!(Some("6 [Church numeral]".into()), decode("#6"));
+        assert_eq!(Some("0 / false [Church numeral / boolean]".into()), decode("Lx y.y"));
+        assert_eq!(Some("true [boolean]".into()), decode("Lx y.x"));
+        assert_eq!(Some("<1, true> [pair]".into()), decode("<#1, Lx y.x>"));
+        assert_eq!(Some("[2, <0, a>, λx.x] [list]".into()), decode("[#2, <#0, a>, Lx.x]"));
+        assert_eq!(Some("\"hi\" [string]".into()), decode("\"hi\""));
+        assert_eq!(Some("[1, 2] [list]".into()), decode("[#1, #2]"));
+        assert_eq!(None, decode("Lx.x"));
+    }
+
+    #[test]
+    fn test_decode_in_notation() {
+        use ::pretty::Notation;
+
+        let term = parse_term("<[#1, Lx y.y x], Lf.f f>");
+        let decode = |notation| {
+            let printer = PrettyPrinter { notation, ..PrettyPrinter::default() };
+            Decoders::default().decode(&term, &printer).unwrap().value
+        };
+
+        assert_eq!("<[1, λx.λy.y x], λf.f f>", decode(Notation::Unicode));
+        assert_eq!("<[1, \\x.\\y.y x], \\f.f f>", decode(Notation::Ascii));
+        assert_eq!("<[1, λ.λ.↑1 ↑2], λ.↑1 ↑1>", decode(Notation::DeBruijn));
+    }
+
+    #[test]
+    fn test_custom_decoder() {
+        struct Identity;
+
+        impl Decoder for Identity {
+            fn name(&self) -> &str {
+                "combinator"
+            }
+
+            fn decode(&self, term: &Term, _: &Decoders, _: &PrettyPrinter) -> Option<String> {
+                if *term == Term::lambda(Term::variable(Name::bound(1))) {
+                    Some("I".into())
+                } else {
+                    None
+                }
+            }
+        }
+
+        let mut decoders = Decoders::default();
+        decoders.add(Box::new(Identity));
+
+        let printer = PrettyPrinter::default();
+        assert_eq!(Some("I [combinator]".into()), decoders.decode(&parse_term("Lx.x"), &printer).map(|d| d.to_string()));
+        assert_eq!("[I, 1]", decoders.decode(&parse_term("[Lx.x, #1]"), &printer).unwrap().value);
+    }
+}
@@ -0,0 +1,367 @@
+//! Encodings of data as lambda terms, used to expand literals and to read
+//! results back.
+
+use ::lambda::{Hint, Name, Term};
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum NumeralEncoding {
+    /// `n` is `λf.λx.f^n x`, iterating `f` n times.
+    Church,
+    /// `0` is `λs.λz.z` and `n+1` is `λs.λz.s n`, which makes the
+    /// predecessor a constant time operation.
+    Scott,
+    /// `0` is `λs.λz.z` and `n+1` is `λs.λz.s n (n s z)`, which supports both
+    /// iteration and a constant time predecessor.
+    Parigot,
+}
+
+impl NumeralEncoding {
+    pub const ALL: [NumeralEncoding; 3] = [
+        NumeralEncoding::Church,
+        NumeralEncoding::Scott,
+        NumeralEncoding::Parigot,
+    ];
+
+    pub fn name(self) -> &'static str {
+        use self::NumeralEncoding::*;
+
+        match self {
+            Church => "church",
+            Scott => "scott",
+            Parigot => "parigot",
+        }
+    }
+
+    /// The largest literal that is expanded. Terms do not share subterms, so
+    /// a Parigot numeral grows exponentially with its value.
+    pub fn max_literal(self) -> u32 {
+        use self::NumeralEncoding::*;
+
+        match self {
+            Church | Scott => 10_000,
+            Parigot => 16,
+        }
+    }
+
+    /// Returns the numeral for `n`, or `None` if `n` exceeds `max_literal`.
+    pub fn encode(self, n: u32) -> Option<Term> {
+        use self::NumeralEncoding::*;
+
+        if n > self.max_literal() {
+            return None;
+        }
+
+        let successor = Term::variable(Name::bound(2));
+        let zero = Term::variable(Name::bound(1));
+
+        let term = match self {
+            Church => {
+                let body = (0..n).fold(zero, |body, _| Term::apply(successor.clone(), body));
+                binders("f", "x", body)
+            }
+            Scott => {
+                (0..n).fold(binders("s", "z", zero), |predecessor, _| {
+                    binders("s", "z", Term::apply(successor.clone(), predecessor))
+                })
+            }
+            Parigot => {
+                (0..n).fold(binders("s", "z", zero.clone()), |predecessor, _| {
+                    let recursion = Term::apply(Term::apply(predecessor.clone(), successor.clone()), zero.clone());
+                    binders("s", "z", Term::apply(Term::apply(successor.clone(), predecessor), recursion))
+                })
+            }
+        };
+
+        Some(term)
+    }
+}
+
+impl Default for NumeralEncoding {
+    fn default() -> NumeralEncoding {
+        NumeralEncoding::Church
+    }
+}
+
+impl fmt::Display for NumeralEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseEncodingError(pub String);
+
+impl FromStr for NumeralEncoding {
+    type Err = ParseEncodingError;
+
+    fn from_str(s: &str) -> Result<NumeralEncoding, ParseEncodingError> {
+        NumeralEncoding::ALL.iter()
+            .find(|encoding| encoding.name() == s)
+            .cloned()
+            .ok_or_else(|| ParseEncodingError(format!("Unknown numeral encoding: {}", s)))
+    }
+}
+
+/// Encodes `elements` as a right-fold list, `[a, b]` becoming
+/// `λc.λn.c a (c b n)`.
+pub fn encode_list(elements: Vec<Term>) -> Term {
+    let cons = Term::variable(Name::bound(2));
+    let body = elements.into_iter().rev().fold(Term::variable(Name::bound(1)), |tail, element| {
+        let element = shift(&element, 2, 0).expect("raising indices can not fail");
+        Term::apply(Term::apply(cons.clone(), element), tail)
+    });
+    binders("c", "n", body)
+}
+
+/// Decodes a right-fold list. Fails if the elements refer to the list's own
+/// binders.
+pub fn decode_list(term: &Term) -> Option<Vec<Term>> {
+    let mut body = match *term {
+        Term::Lambda { ref body, .. } => match **body {
+            Term::Lambda { ref body, .. } => body,
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    let mut elements = vec![];
+    loop {
+        match **body {
+            Term::Variable { name: Name::Bound { depth: 1 } } => return Some(elements),
+            Term::Application { ref applicand, ref argument } => {
+                match **applicand {
+                    Term::Application { applicand: ref cons, argument: ref element } => {
+                        if **cons != Term::variable(Name::bound(2)) {
+                            return None;
+                        }
+                        elements.push(shift(element, -2, 0)?);
+                        body = argument;
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Encodes `elements` as a tuple, `<a, b>` becoming `λs.s a b`.
+pub fn encode_tuple(elements: Vec<Term>) -> Term {
+    let body = elements.into_iter().fold(Term::variable(Name::bound(1)), |body, element| {
+        Term::apply(body, shift(&element, 1, 0).expect("raising indices can not fail"))
+    });
+    Term::named_lambda(Hint::new("s"), body)
+}
+
+/// Decodes a tuple. Fails if the elements refer to the tuple's own binder.
+pub fn decode_tuple(term: &Term) -> Option<Vec<Term>> {
+    let mut body = match *term {
+        Term::Lambda { ref body, .. } => body,
+        _ => return None,
+    };
+
+    let mut elements = vec![];
+    loop {
+        match **body {
+            Term::Variable { name: Name::Bound { depth: 1 } } => {
+                elements.reverse();
+                return Some(elements);
+            }
+            Term::Application { ref applicand, ref argument } => {
+                elements.push(shift(argument, -1, 0)?);
+                body = applicand;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// A term with a bracketed notation, as printed back.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Literal {
+    /// A non-empty list `[a, b]`.
+    List(Vec<Term>),
+    /// A tuple of at least two elements `<a, b>`.
+    Tuple(Vec<Term>),
+}
+
+impl Literal {
+    pub fn delimiters(&self) -> (&'static str, &'static str) {
+        match *self {
+            Literal::List(_) => ("[", "]"),
+            Literal::Tuple(_) => ("<", ">"),
+        }
+    }
+
+    pub fn elements(&self) -> &[Term] {
+        match *self {
+            Literal::List(ref elements) | Literal::Tuple(ref elements) => elements,
+        }
+    }
+}
+
+/// Decodes a term to be printed as a list or tuple. The smaller lists and
+/// tuples are also numerals, booleans or the identity, so they are left alone.
+pub fn decode_literal(term: &Term) -> Option<Literal> {
+    match decode_list(term) {
+        Some(elements) if !elements.is_empty() => Some(Literal::List(elements)),
+        _ => match decode_tuple(term) {
+            Some(elements) if elements.len() >= 2 => Some(Literal::Tuple(elements)),
+            _ => None,
+        }
+    }
+}
+
+/// Decodes a Church numeral.
+pub fn decode_numeral(term: &Term) -> Option<u32> {
+    let mut body = match *term {
+        Term::Lambda { ref body, .. } => match **body {
+            Term::Lambda { ref body, .. } => body,
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    let mut n = 0;
+    loop {
+        match **body {
+            Term::Variable { name: Name::Bound { depth: 1 } } => return Some(n),
+            Term::Application { ref applicand, ref argument } if **applicand == Term::variable(Name::bound(2)) => {
+                n += 1;
+                body = argument;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Encodes `text` as a list of the Church numerals of its characters. Fails
+/// if a character is beyond the largest Church literal.
+pub fn encode_string(text: &str) -> Option<Term> {
+    let characters = text.chars()
+        .map(|c| NumeralEncoding::Church.encode(c as u32))
+        .collect::<Option<Vec<_>>>()?;
+    Some(encode_list(characters))
+}
+
+/// Decodes a non-empty list of Church numerals as text. The empty list is
+/// also the numeral zero and false, so it is not taken for a string, and
+/// neither are lists with control characters, which are more likely lists of
+/// small numbers.
+pub fn decode_string(term: &Term) -> Option<String> {
+    let elements = decode_list(term)?;
+    if elements.is_empty() {
+        return None;
+    }
+
+    elements.iter()
+        .map(|element| {
+            decode_numeral(element)
+                .and_then(::std::char::from_u32)
+                .filter(|c| !c.is_control() || c.is_whitespace())
+        })
+        .collect()
+}
+
+/// Wraps `body` in two lambdas binding `outer` and `inner`.
+fn binders(outer: &str, inner: &str, body: Term) -> Term {
+    Term::named_lambda(Hint::new(outer), Term::named_lambda(Hint::new(inner), body))
+}
+
+/// Adds `by` to the indices of variables bound outside of `term`, counting
+/// `depth` binders around it as inside. Fails if lowering the indices would
+/// capture a variable by one of the removed binders.
+fn shift(term: &Term, by: i32, depth: u32) -> Option<Term> {
+    match *term {
+        Term::Variable { name: Name::Bound { depth: index } } if index > depth => {
+            let shifted = index as i32 + by;
+            if shifted <= depth as i32 {
+                None
+            } else {
+                Some(Term::variable(Name::bound(shifted as u32)))
+            }
+        }
+        Term::Variable { .. } => Some(term.clone()),
+        Term::Lambda { ref body, ref hint } => Some(Term::named_lambda(hint.clone(), shift(body, by, depth + 1)?)),
+        Term::Application { ref applicand, ref argument } => {
+            Some(Term::apply(shift(applicand, by, depth)?, shift(argument, by, depth)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ::lexer::Token;
+    use ::parser::parse;
+    use ::runtime::Statement;
+
+    fn parse_term(input: &str) -> Term {
+        match parse(&Token::parse_all(input).unwrap()) {
+            Ok(Statement::Expression(term)) => term,
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_numerals() {
+        use self::NumeralEncoding::*;
+
+        assert_eq!(Some(parse_term("Lf x.x")), Church.encode(0));
+        assert_eq!(Some(parse_term("Lf x.f (f (f x))")), Church.encode(3));
+        assert_eq!(Some(parse_term("Ls z.z")), Scott.encode(0));
+        assert_eq!(Some(parse_term("Ls z.s (Ls z.s (Ls z.z))")), Scott.encode(2));
+        assert_eq!(
+            Some(parse_term("Ls z.s (Ls z.s (Ls z.z) ((Ls z.z) s z)) ((Ls z.s (Ls z.z) ((Ls z.z) s z)) s z)")),
+            Parigot.encode(2),
+        );
+        assert_eq!(None, Parigot.encode(Parigot.max_literal() + 1));
+    }
+
+    #[test]
+    fn test_encoding_names() {
+        for &encoding in NumeralEncoding::ALL.iter() {
+            assert_eq!(Ok(encoding), encoding.name().parse());
+        }
+        assert!("peano".parse::<NumeralEncoding>().is_err());
+    }
+
+    #[test]
+    fn test_lists() {
+        let list = encode_list(vec![parse_term("a"), parse_term("Lx.x")]);
+        assert_eq!(parse_term("Lc n.c a (c (Lx.x) n)"), list);
+        assert_eq!(Some(vec![parse_term("a"), parse_term("Lx.x")]), decode_list(&list));
+        assert_eq!(Some(vec![]), decode_list(&parse_term("Lc n.n")));
+        assert_eq!(None, decode_list(&parse_term("Lc n.c n n")));
+
+        // an element referring to a binder outside of the list
+        let outer = Term::lambda(encode_list(vec![Term::variable(Name::bound(1))]));
+        assert_eq!(parse_term("Lx c n.c x n"), outer);
+    }
+
+    #[test]
+    fn test_tuples() {
+        let tuple = encode_tuple(vec![parse_term("a"), parse_term("Lx.x"), parse_term("b")]);
+        assert_eq!(parse_term("Ls.s a (Lx.x) b"), tuple);
+        assert_eq!(Some(vec![parse_term("a"), parse_term("Lx.x"), parse_term("b")]), decode_tuple(&tuple));
+        assert_eq!(Some(vec![]), decode_tuple(&parse_term("Ls.s")));
+        assert_eq!(None, decode_tuple(&parse_term("Ls.s s")));
+        assert_eq!(None, decode_tuple(&parse_term("Ls.a s")));
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(Some(3), decode_numeral(&parse_term("Lf x.f (f (f x))")));
+        assert_eq!(None, decode_numeral(&parse_term("Lf x.f f")));
+
+        let text = encode_string("hi λ").unwrap();
+        assert_eq!(Some("hi λ".to_owned()), decode_string(&text));
+        assert_eq!(None, decode_string(&encode_string("").unwrap()));
+        assert_eq!(Some("a\tb\n".to_owned()), decode_string(&encode_string("a\tb\n").unwrap()));
+        assert_eq!(None, decode_string(&encode_string("\u{1}\u{2}").unwrap()));
+        assert_eq!(None, encode_string("😀"));
+    }
+}
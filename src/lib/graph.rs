@@ -0,0 +1,355 @@
+//! Call-by-need evaluation by graph reduction.
+//!
+//! A term is converted into a graph in which every β-reduction substitutes a
+//! *pointer* to the argument instead of a copy of it. Whenever a shared
+//! argument is reduced, the result is written back into its node, so every
+//! occurrence of the argument sees the reduction and it is performed only
+//! once. Redexes are chosen leftmost-outermost, so the graph reaches the same
+//! normal form as `Strategy::NormalOrder`.
+
+use ::lambda::{Hint, Name, Term};
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+type Binder = u32;
+type NodeRef = Rc<RefCell<Vertex>>;
+
+#[derive(Debug, Clone)]
+enum Variable {
+    Bound(Binder),
+    Free(String),
+    /// A de Bruijn index pointing outside the converted term.
+    Loose(u32),
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Variable(Variable),
+    Lambda { binder: Binder, body: NodeRef, hint: Hint },
+    Application { applicand: NodeRef, argument: NodeRef },
+    /// A contracted redex, forwarding to its result.
+    Indirection(NodeRef),
+}
+
+#[derive(Debug)]
+struct Vertex {
+    node: Node,
+    /// Set once the subgraph is known to contain no redex. Only redexes are
+    /// ever overwritten, so a normal subgraph stays normal.
+    normal: bool,
+    /// Cached binders occurring free in the subgraph. Reductions can only
+    /// remove free occurrences, so a stale set is a safe superset.
+    free: Option<Rc<HashSet<Binder>>>,
+}
+
+fn vertex(node: Node) -> NodeRef {
+    Rc::new(RefCell::new(Vertex { node, normal: false, free: None }))
+}
+
+fn resolve(node: &NodeRef) -> NodeRef {
+    let mut node = node.clone();
+    loop {
+        let next = match node.borrow().node {
+            Node::Indirection(ref target) => target.clone(),
+            _ => return node.clone(),
+        };
+        node = next;
+    }
+}
+
+pub struct Graph {
+    root: NodeRef,
+    next_binder: Binder,
+}
+
+impl Graph {
+    pub fn new(term: &Term) -> Graph {
+        let mut graph = Graph { root: vertex(Node::Variable(Variable::Loose(0))), next_binder: 0 };
+        graph.root = graph.build(term, &mut vec![]);
+        graph
+    }
+
+    fn fresh_binder(&mut self) -> Binder {
+        self.next_binder += 1;
+        self.next_binder
+    }
+
+    fn build(&mut self, term: &Term, binders: &mut Vec<Binder>) -> NodeRef {
+        match *term {
+            Term::Variable { name: Name::Free { ref name } } => {
+                vertex(Node::Variable(Variable::Free(name.clone())))
+            }
+            Term::Variable { name: Name::Bound { depth } } => {
+                let depth = depth as usize;
+                if depth <= binders.len() {
+                    vertex(Node::Variable(Variable::Bound(binders[binders.len() - depth])))
+                } else {
+                    vertex(Node::Variable(Variable::Loose((depth - binders.len()) as u32)))
+                }
+            }
+            Term::Lambda { ref body, ref hint } => {
+                let binder = self.fresh_binder();
+                binders.push(binder);
+                let body = self.build(body, binders);
+                binders.pop();
+                vertex(Node::Lambda { binder, body, hint: hint.clone() })
+            }
+            Term::Application { ref applicand, ref argument } => {
+                let applicand = self.build(applicand, binders);
+                let argument = self.build(argument, binders);
+                vertex(Node::Application { applicand, argument })
+            }
+        }
+    }
+
+    /// Contracts the leftmost-outermost redex. Returns `false` if the graph
+    /// is in normal form.
+    pub fn step(&mut self) -> bool {
+        let root = self.root.clone();
+        self.step_at(&root)
+    }
+
+    fn step_at(&mut self, node: &NodeRef) -> bool {
+        let node = resolve(node);
+        if node.borrow().normal {
+            return false;
+        }
+
+        let contents = node.borrow().node.clone();
+        let reduced = match contents {
+            Node::Variable(_) => false,
+            Node::Lambda { body, .. } => self.step_at(&body),
+            Node::Application { applicand, argument } => {
+                let head = resolve(&applicand);
+                let redex = match head.borrow().node {
+                    Node::Lambda { binder, ref body, .. } => Some((binder, body.clone())),
+                    _ => None,
+                };
+
+                if let Some((binder, body)) = redex {
+                    let result = self.instantiate(&body, binder, &argument);
+                    node.borrow_mut().node = Node::Indirection(result);
+                    return true;
+                }
+
+                self.step_at(&applicand) || self.step_at(&argument)
+            }
+            Node::Indirection(_) => unreachable!(),
+        };
+
+        if !reduced {
+            node.borrow_mut().normal = true;
+        }
+        reduced
+    }
+
+    /// Copies `body` with `binder` replaced by a pointer to `argument`.
+    /// Subgraphs not mentioning any substituted binder are shared, and every
+    /// copied lambda receives a fresh binder so binders stay unique.
+    fn instantiate(&mut self, body: &NodeRef, binder: Binder, argument: &NodeRef) -> NodeRef {
+        let mut substitution = HashMap::new();
+        substitution.insert(binder, argument.clone());
+        self.copy(body, &mut substitution)
+    }
+
+    fn copy(&mut self, node: &NodeRef, substitution: &mut HashMap<Binder, NodeRef>) -> NodeRef {
+        let node = resolve(node);
+        let mentioned = {
+            let free = free_binders(&node);
+            substitution.keys().any(|binder| free.contains(binder))
+        };
+        if !mentioned {
+            return node;
+        }
+
+        let contents = node.borrow().node.clone();
+        match contents {
+            Node::Variable(Variable::Bound(binder)) => substitution[&binder].clone(),
+            Node::Variable(_) => node,
+            Node::Lambda { binder, body, hint } => {
+                let fresh = self.fresh_binder();
+                substitution.insert(binder, vertex(Node::Variable(Variable::Bound(fresh))));
+                let body = self.copy(&body, substitution);
+                substitution.remove(&binder);
+                vertex(Node::Lambda { binder: fresh, body, hint })
+            }
+            Node::Application { applicand, argument } => {
+                let applicand = self.copy(&applicand, substitution);
+                let argument = self.copy(&argument, substitution);
+                vertex(Node::Application { applicand, argument })
+            }
+            Node::Indirection(_) => unreachable!(),
+        }
+    }
+
+    /// Reads the graph back as a term, unsharing all shared subgraphs.
+    pub fn to_term(&self) -> Term {
+        read_back(&self.root, &mut vec![])
+    }
+}
+
+fn free_binders(node: &NodeRef) -> Rc<HashSet<Binder>> {
+    let node = resolve(node);
+    if let Some(ref free) = node.borrow().free {
+        return free.clone();
+    }
+
+    let contents = node.borrow().node.clone();
+    let free = match contents {
+        Node::Variable(Variable::Bound(binder)) => {
+            let mut free = HashSet::new();
+            free.insert(binder);
+            Rc::new(free)
+        }
+        Node::Variable(_) => Rc::new(HashSet::new()),
+        Node::Lambda { binder, body, .. } => {
+            let body = free_binders(&body);
+            if body.contains(&binder) {
+                let mut free = (*body).clone();
+                free.remove(&binder);
+                Rc::new(free)
+            } else {
+                body
+            }
+        }
+        Node::Application { applicand, argument } => {
+            let applicand = free_binders(&applicand);
+            let argument = free_binders(&argument);
+            if argument.is_subset(&applicand) {
+                applicand
+            } else if applicand.is_subset(&argument) {
+                argument
+            } else {
+                Rc::new(applicand.union(&argument).cloned().collect())
+            }
+        }
+        Node::Indirection(_) => unreachable!(),
+    };
+
+    node.borrow_mut().free = Some(free.clone());
+    free
+}
+
+fn read_back(node: &NodeRef, binders: &mut Vec<Binder>) -> Term {
+    let node = resolve(node);
+    let contents = node.borrow().node.clone();
+    match contents {
+        Node::Variable(Variable::Bound(binder)) => {
+            let position = binders.iter().rposition(|&b| b == binder)
+                .expect("bound variable outside of its binder");
+            Term::variable(Name::bound((binders.len() - position) as u32))
+        }
+        Node::Variable(Variable::Free(name)) => Term::variable(Name::free(name)),
+        Node::Variable(Variable::Loose(depth)) => {
+            Term::variable(Name::bound(depth + binders.len() as u32))
+        }
+        Node::Lambda { binder, body, hint } => {
+            binders.push(binder);
+            let body = read_back(&body, binders);
+            binders.pop();
+            Term::named_lambda(hint, body)
+        }
+        Node::Application { applicand, argument } => {
+            Term::apply(read_back(&applicand, binders), read_back(&argument, binders))
+        }
+        Node::Indirection(_) => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ::lambda::{EvalResult, Strategy};
+    use ::lexer::Token;
+    use ::runtime::Environment;
+
+    fn free(name: &str) -> Term {
+        Term::variable(Name::free(name.into()))
+    }
+
+    fn bound(depth: u32) -> Term {
+        Term::variable(Name::bound(depth))
+    }
+
+    fn normalize(term: &Term) -> (Term, usize) {
+        let mut graph = Graph::new(term);
+        let mut steps = 0;
+        while graph.step() {
+            steps += 1;
+        }
+        (graph.to_term(), steps)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let term = Term::lambda(Term::apply(
+            Term::lambda(Term::apply(bound(1), bound(2))),
+            Term::apply(free("a"), bound(3)),
+        ));
+
+        assert_eq!(term, Graph::new(&term).to_term());
+    }
+
+    #[test]
+    fn test_argument_is_shared() {
+        // ((λx.(f x x)) (I a)) reduces the argument once
+        let i = Term::lambda(bound(1));
+        let term = Term::apply(
+            Term::lambda(Term::apply(Term::apply(free("f"), bound(1)), bound(1))),
+            Term::apply(i, free("a")),
+        );
+
+        assert_eq!(
+            (Term::apply(Term::apply(free("f"), free("a")), free("a")), 2),
+            normalize(&term),
+        );
+    }
+
+    #[test]
+    fn test_same_normal_form_as_normal_order() {
+        // ((λx.(λy.(x (x y)))) (λz.(λw.(z w)))) needs renaming of copied binders
+        let twice = Term::lambda(Term::lambda(Term::apply(bound(2), Term::apply(bound(2), bound(1)))));
+        let apply = Term::lambda(Term::lambda(Term::apply(bound(2), bound(1))));
+        let term = Term::apply(twice, apply);
+
+        let mut expected = term.clone();
+        loop {
+            match expected.reduce(Strategy::NormalOrder) {
+                EvalResult::NormalForm(t) => { expected = t; break; }
+                EvalResult::Reduced { term: t, .. } => expected = t,
+            }
+        }
+
+        assert_eq!(expected, normalize(&term).0);
+    }
+
+    #[test]
+    fn test_prelude_needs_fewer_reductions() {
+        let mut environment: Environment = Environment::new();
+        // a statement is one line, unless a block comment continues it
+        let mut statement = String::new();
+        for line in include_str!("../../prelude.lmd").lines() {
+            statement.push_str(line);
+            statement.push('\n');
+            match Token::parse_all(&statement) {
+                Err(ref e) if e.incomplete => continue,
+                Ok(ref tokens) if tokens.is_empty() => {}
+                _ => { environment.eval_str(&statement).unwrap(); }
+            }
+            statement.clear();
+        }
+
+        for input in &["fact #3", "gcd #4 #2"] {
+            environment.strategy = Strategy::NormalOrder;
+            let normal_order = environment.eval_str(input).unwrap();
+            environment.strategy = Strategy::CallByNeed;
+            let call_by_need = environment.eval_str(input).unwrap();
+
+            assert_eq!(normal_order.normal_form, call_by_need.normal_form, "{}", input);
+            assert!(call_by_need.reductions < normal_order.reductions, "{}: {} reductions by need, {} in normal order",
+                    input, call_by_need.reductions, normal_order.reductions);
+        }
+    }
+}
@@ -1,6 +1,12 @@
-use ::runtime::SymbolTable;
+use ::encoding;
+use ::runtime::{CancellationToken, SymbolTable};
 
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::Arc;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum Name {
@@ -49,10 +55,38 @@ impl fmt::Display for Name {
     }
 }
 
+/// The name a lambda's binder was given in the source, used when printing.
+/// Hints take no part in comparisons, so α-equivalent terms stay equal.
+#[derive(Debug, Clone, Default)]
+pub struct Hint(Option<Arc<str>>);
+
+impl Hint {
+    pub fn new(name: &str) -> Hint {
+        Hint(Some(Arc::from(name)))
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.0.as_ref().map(|s| &**s)
+    }
+}
+
+impl PartialEq for Hint {
+    fn eq(&self, _: &Hint) -> bool {
+        true
+    }
+}
+
+impl Eq for Hint {}
+
+impl Hash for Hint {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Term {
     Lambda {
-        body: Box<Term>
+        body: Box<Term>,
+        hint: Hint,
     },
     Application {
         applicand: Box<Term>,
@@ -64,12 +98,192 @@ pub enum Term {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub enum Strategy { NormalOrder, ApplicativeOrder }
+pub enum Strategy {
+    /// Leftmost-outermost reduction to β-normal form.
+    NormalOrder,
+    /// Leftmost-innermost reduction to β-normal form.
+    ApplicativeOrder,
+    /// Arguments are reduced before being substituted; lambda bodies are not
+    /// reduced.
+    CallByValue,
+    /// Arguments are substituted unreduced, and reduction stops at weak head
+    /// normal form: neither lambda bodies nor arguments are reduced, so this
+    /// takes the same steps as `WeakHeadNormal`.
+    CallByName,
+    /// Leftmost-outermost reduction to head normal form.
+    HeadNormal,
+    /// Leftmost-outermost reduction to weak head normal form.
+    WeakHeadNormal,
+    /// Normal order reduction with shared arguments. Sharing requires a graph
+    /// (see `graph::Graph`), so on a single `Term` this steps like
+    /// `NormalOrder`.
+    CallByNeed,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 7] = [
+        Strategy::NormalOrder,
+        Strategy::ApplicativeOrder,
+        Strategy::CallByValue,
+        Strategy::CallByName,
+        Strategy::HeadNormal,
+        Strategy::WeakHeadNormal,
+        Strategy::CallByNeed,
+    ];
 
+    pub fn name(self) -> &'static str {
+        use self::Strategy::*;
+
+        match self {
+            NormalOrder => "normal",
+            ApplicativeOrder => "applicative",
+            CallByValue => "value",
+            CallByName => "name",
+            HeadNormal => "head",
+            WeakHeadNormal => "whnf",
+            CallByNeed => "need",
+        }
+    }
+
+    fn reduces_under_lambda(self) -> bool {
+        use self::Strategy::*;
+
+        match self {
+            NormalOrder | ApplicativeOrder | HeadNormal | CallByNeed => true,
+            CallByValue | CallByName | WeakHeadNormal => false,
+        }
+    }
+
+    fn evaluates_arguments_first(self) -> bool {
+        use self::Strategy::*;
+
+        match self {
+            ApplicativeOrder | CallByValue => true,
+            _ => false,
+        }
+    }
+
+    fn reduces_arguments(self) -> bool {
+        use self::Strategy::*;
+
+        match self {
+            NormalOrder | CallByNeed => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseStrategyError(pub String);
+
+impl FromStr for Strategy {
+    type Err = ParseStrategyError;
+
+    fn from_str(s: &str) -> Result<Strategy, ParseStrategyError> {
+        Strategy::ALL.iter()
+            .find(|strategy| strategy.name() == s)
+            .cloned()
+            .ok_or_else(|| ParseStrategyError(format!("Unknown strategy: {}", s)))
+    }
+}
+
+/// How evaluation uses the η rule `λx.M x = M` besides β-reduction.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Eta {
+    Off,
+    /// Contracts `λx.M x` to `M` where `x` does not occur in `M`, which
+    /// leads to βη-normal forms.
+    Reduce,
+    /// Expands a result that is not a lambda once, `M` becoming `λx.M x`.
+    Expand,
+}
+
+impl Eta {
+    pub const ALL: [Eta; 3] = [Eta::Off, Eta::Reduce, Eta::Expand];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Eta::Off => "off",
+            Eta::Reduce => "reduce",
+            Eta::Expand => "expand",
+        }
+    }
+
+    /// Applies the η rule to a term without β-redexes, if `self` asks for
+    /// it. Expansion only applies to terms which are not lambdas, so it
+    /// happens at most once.
+    pub fn step(self, term: Term, strategy: Strategy) -> EvalResult {
+        match self {
+            Eta::Off => EvalResult::NormalForm(term),
+            Eta::Reduce => term.eta_reduce(strategy),
+            Eta::Expand => match term {
+                Term::Lambda { .. } => EvalResult::NormalForm(term),
+                _ => EvalResult::Reduced { term: term.eta_expand(), redex: vec![] },
+            },
+        }
+    }
+}
+
+impl Default for Eta {
+    fn default() -> Eta {
+        Eta::Off
+    }
+}
+
+impl fmt::Display for Eta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseEtaError(pub String);
+
+impl FromStr for Eta {
+    type Err = ParseEtaError;
+
+    fn from_str(s: &str) -> Result<Eta, ParseEtaError> {
+        Eta::ALL.iter()
+            .find(|eta| eta.name() == s)
+            .cloned()
+            .ok_or_else(|| ParseEtaError(format!("Unknown η mode: {}", s)))
+    }
+}
+
+/// The rule applied by a reduction step.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Rule {
+    Beta,
+    Eta,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Rule::Beta => write!(f, "β"),
+            Rule::Eta => write!(f, "η"),
+        }
+    }
+}
+
+/// The largest `n` written as `f^n x`. The parser expands powers, so like
+/// numeral literals they are limited.
+pub const MAX_POWER: u32 = 10_000;
+
+/// The outcome of a single reduction step.
 #[derive(Debug, Clone, PartialEq)]
 pub enum EvalResult {
+    /// No redex was found, so the term is unchanged.
     NormalForm(Term),
-    PossiblyReducible(Term)
+    /// The redex at `redex` in the original term was contracted, giving
+    /// `term`.
+    Reduced { term: Term, redex: Path },
 }
 
 impl EvalResult {
@@ -78,7 +292,7 @@ impl EvalResult {
 
         match self {
             NormalForm(t) => t,
-            PossiblyReducible(t)  => t
+            Reduced { term, .. } => term,
         }
     }
 
@@ -87,18 +301,161 @@ impl EvalResult {
 
         match self {
             NormalForm(t) => NormalForm(f(t)),
-            PossiblyReducible(t)  => PossiblyReducible(f(t))
+            Reduced { term, redex } => Reduced { term: f(term), redex },
+        }
+    }
+}
+
+/// A step below the root of a term, the path to whose redex is kept apart.
+enum Step {
+    Normal(Term),
+    Reduced(Term),
+}
+
+impl Step {
+    fn map<F: FnOnce(Term) -> Term>(self, f: F) -> Step {
+        match self {
+            Step::Normal(t) => Step::Normal(f(t)),
+            Step::Reduced(t) => Step::Reduced(f(t)),
+        }
+    }
+
+    /// Turns the step into the result of a step at the root, given the path
+    /// to its redex.
+    fn at(self, redex: Path) -> EvalResult {
+        match self {
+            Step::Normal(t) => EvalResult::NormalForm(t),
+            Step::Reduced(term) => EvalResult::Reduced { term, redex },
+        }
+    }
+}
+
+/// A step from a term to one of its immediate subterms.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Direction {
+    /// From a lambda to its body.
+    Body,
+    Applicand,
+    Argument,
+}
+
+/// The position of a subterm, as the steps leading to it from the root.
+pub type Path = Vec<Direction>;
+
+/// One of the two terms compared by `Term::equivalent`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Side::Left => write!(f, "left"),
+            Side::Right => write!(f, "right"),
+        }
+    }
+}
+
+/// The answer of `Term::equivalent`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Equivalence {
+    /// The terms reduce to a common term.
+    Equal,
+    Different(Difference),
+    /// Reduction did not tell whether the terms are equal.
+    Unknown(Undecided),
+}
+
+/// Why two terms are not equal. Both reasons rely on the Church-Rosser
+/// theorem: equal terms reduce to a common term.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Difference {
+    /// The terms have the distinct normal forms `left` and `right`.
+    NormalForms { left: Term, right: Term },
+    /// Only the term on `side` has a normal form; the other one was found
+    /// to reduce to itself under normal order reduction.
+    NormalFormOnly { side: Side },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Undecided {
+    /// Neither term has a normal form, and no common reduct was found.
+    NoNormalForms,
+    /// The term on `side` did not reach a normal form within the reduction
+    /// limit.
+    TooManyReductions { side: Side },
+    /// The check was cancelled through its `CancellationToken`.
+    Interrupted,
+}
+
+impl fmt::Display for Equivalence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Equivalence::Equal => write!(f, "equal"),
+            Equivalence::Different(ref difference) => write!(f, "different: {}", difference),
+            Equivalence::Unknown(ref undecided) => write!(f, "unknown: {}", undecided),
+        }
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Difference::NormalForms { .. } => write!(f, "the normal forms differ"),
+            Difference::NormalFormOnly { side } => write!(f, "only the {} side has a normal form", side),
         }
     }
 }
 
+impl fmt::Display for Undecided {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Undecided::NoNormalForms => write!(f, "neither side has a normal form"),
+            Undecided::TooManyReductions { side } => {
+                write!(f, "the {} side has no normal form within the reduction limit", side)
+            }
+            Undecided::Interrupted => write!(f, "interrupted"),
+        }
+    }
+}
+
+/// How the normal order reduction of one side of an equivalence check
+/// ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outcome {
+    NormalForm,
+    Cycle,
+    TooManyReductions,
+}
+
+/// The normal order reduction of one side of an equivalence check.
+struct Reduction {
+    term: Term,
+    /// Every term the reduction passed through, to find common reducts.
+    seen: HashSet<Term>,
+    steps: usize,
+    outcome: Option<Outcome>,
+}
+
+impl Reduction {
+    fn new(term: &Term) -> Reduction {
+        Reduction { term: term.clone(), seen: Some(term.clone()).into_iter().collect(), steps: 0, outcome: None }
+    }
+}
+
 impl Term {
     pub fn variable<T: Into<Name>>(name: T) -> Term {
         Term::Variable { name: name.into() }
     }
 
     pub fn lambda(body: Term) -> Term {
-        Term::Lambda { body: Box::new(body) }
+        Term::named_lambda(Hint::default(), body)
+    }
+
+    pub fn named_lambda(hint: Hint, body: Term) -> Term {
+        Term::Lambda { body: Box::new(body), hint }
     }
 
     pub fn apply(applicand: Term, argument: Term) -> Term {
@@ -116,7 +473,7 @@ impl Term {
                 applicand.rebind_free(deepen_by, depth);
                 argument.rebind_free(deepen_by, depth);
             }
-            Term::Lambda { ref mut body } => {
+            Term::Lambda { ref mut body, .. } => {
                 body.rebind_free(deepen_by, depth + 1);
             }
         }
@@ -137,8 +494,8 @@ impl Term {
                 let argument = argument.substitute(depth, deepen_by, with);
                 Term::apply(applicand, argument)
             }
-            Term::Lambda { body } => {
-                Term::lambda(body.substitute(depth + 1, deepen_by + 1, with))
+            Term::Lambda { body, hint } => {
+                Term::named_lambda(hint, body.substitute(depth + 1, deepen_by + 1, with))
             }
         }
     }
@@ -152,7 +509,7 @@ impl Term {
                 }
             } => identifier == variable,
             Term::Variable { .. } => false,
-            Term::Lambda { ref body } => body.is_free_in(variable),
+            Term::Lambda { ref body, .. } => body.is_free_in(variable),
             Term::Application {
                 ref applicand,
                 ref argument,
@@ -160,6 +517,43 @@ impl Term {
         }
     }
 
+    /// Returns the names of all free variables, sorted and without duplicates.
+    pub fn free_variables(&self) -> Vec<String> {
+        fn collect<'a>(term: &'a Term, names: &mut Vec<&'a String>) {
+            match *term {
+                Term::Variable { name: Name::Free { ref name } } => names.push(name),
+                Term::Variable { .. } => {}
+                Term::Lambda { ref body, .. } => collect(body, names),
+                Term::Application { ref applicand, ref argument } => {
+                    collect(applicand, names);
+                    collect(argument, names);
+                }
+            }
+        }
+
+        let mut names = vec![];
+        collect(self, &mut names);
+        names.sort();
+        names.dedup();
+        names.into_iter().cloned().collect()
+    }
+
+    /// Whether the term has neither free variables nor de Bruijn indices
+    /// pointing outside of it.
+    pub fn is_closed(&self) -> bool {
+        fn closed_at(term: &Term, depth: u32) -> bool {
+            match *term {
+                Term::Variable { ref name } => !name.free_for(depth),
+                Term::Lambda { ref body, .. } => closed_at(body, depth + 1),
+                Term::Application { ref applicand, ref argument } => {
+                    closed_at(applicand, depth) && closed_at(argument, depth)
+                }
+            }
+        }
+
+        closed_at(self, 0)
+    }
+
     pub fn bind_free_from(self, symbols: &impl SymbolTable) -> Term {
         match self {
             Term::Variable {
@@ -171,8 +565,8 @@ impl Term {
                     .unwrap_or_else(|| Term::variable(Name::free(identifier)))
             }
             v @ Term::Variable { .. } => v,
-            Term::Lambda { body } => {
-                Term::lambda(body.bind_free_from(symbols))
+            Term::Lambda { body, hint } => {
+                Term::named_lambda(hint, body.bind_free_from(symbols))
             }
             Term::Application { applicand, argument } => {
                 Term::apply(
@@ -183,41 +577,153 @@ impl Term {
         }
     }
 
+    fn contract(body: Term, argument: Term) -> Term {
+        let mut body = body.substitute(1, 1, argument);
+        body.rebind_free(-1, 0);
+        body
+    }
+
+    /// Performs a single reduction step according to `strategy`.
+    ///
+    /// `NormalForm` is returned when the strategy finds no redex to contract,
+    /// which for the weak strategies does not mean that the term is in
+    /// β-normal form.
     pub fn reduce(self, strategy: Strategy) -> EvalResult {
-        use self::EvalResult::*;
+        let mut path = vec![];
+        self.reduce_along(strategy, &mut path).at(path)
+    }
 
-        match strategy {
-            Strategy::NormalOrder => {
-                match self {
-                    v @ Term::Variable { .. } =>
-                        NormalForm(v),
-                    Term::Lambda { body } =>
-                        body.reduce(strategy).map(Term::lambda),
-                    Term::Application { applicand, argument } => {
-                        let applicand = *applicand;
-                        let argument = *argument;
-                        if let Term::Lambda { body } = applicand {
-                            let mut body = body.substitute(1, 1, argument);
-                            body.rebind_free(-1, 0);
-                            return PossiblyReducible(body);
-                        } else {
-                            let head = applicand.reduce(strategy);
+    /// Reduces `self`, leaving the path to the contracted redex appended to
+    /// `path`. Nothing is appended if no step was made.
+    fn reduce_along(self, strategy: Strategy, path: &mut Path) -> Step {
+        use self::Step::*;
 
-                            match head {
-                                PossiblyReducible(_) =>
-                                    return head.map(|t| Term::apply(t, argument)),
-                                NormalForm(head) =>
-                                    return argument.reduce(strategy).map(|t| Term::apply(head, t))
+        // descends into a subterm, forgetting the step if it found no redex
+        fn within(path: &mut Path, direction: Direction, term: Term, strategy: Strategy) -> Step {
+            path.push(direction);
+            let result = term.reduce_along(strategy, path);
+            if let Normal(_) = result {
+                path.pop();
+            }
+            result
+        }
+
+        match self {
+            v @ Term::Variable { .. } => Normal(v),
+            Term::Lambda { body, hint } => {
+                if strategy.reduces_under_lambda() {
+                    within(path, Direction::Body, *body, strategy).map(|body| Term::named_lambda(hint, body))
+                } else {
+                    Normal(Term::named_lambda(hint, *body))
+                }
+            }
+            Term::Application { applicand, argument } => {
+                let applicand = *applicand;
+                let argument = *argument;
+
+                if strategy.evaluates_arguments_first() {
+                    // innermost: the applicand and argument are fully reduced
+                    // before the application itself is contracted
+                    match within(path, Direction::Applicand, applicand, strategy) {
+                        Reduced(t) => Reduced(Term::apply(t, argument)),
+                        Normal(applicand) => match within(path, Direction::Argument, argument, strategy) {
+                            Reduced(t) => Reduced(Term::apply(applicand, t)),
+                            Normal(argument) => {
+                                if let Term::Lambda { body, .. } = applicand {
+                                    Reduced(Term::contract(*body, argument))
+                                } else {
+                                    Normal(Term::apply(applicand, argument))
+                                }
                             }
                         }
                     }
-                } 
+                } else if let Term::Lambda { body, .. } = applicand {
+                    Reduced(Term::contract(*body, argument))
+                } else {
+                    match within(path, Direction::Applicand, applicand, strategy) {
+                        Reduced(t) => Reduced(Term::apply(t, argument)),
+                        Normal(head) => {
+                            if strategy.reduces_arguments() {
+                                within(path, Direction::Argument, argument, strategy).map(|t| Term::apply(head, t))
+                            } else {
+                                Normal(Term::apply(head, argument))
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The paths to all β-redexes in `self`, outermost first and otherwise
+    /// from left to right.
+    pub fn redexes(&self) -> Vec<Path> {
+        fn collect(term: &Term, path: &mut Path, redexes: &mut Vec<Path>) {
+            match *term {
+                Term::Variable { .. } => {}
+                Term::Lambda { ref body, .. } => {
+                    path.push(Direction::Body);
+                    collect(body, path, redexes);
+                    path.pop();
+                }
+                Term::Application { ref applicand, ref argument } => {
+                    if let Term::Lambda { .. } = **applicand {
+                        redexes.push(path.clone());
+                    }
+                    path.push(Direction::Applicand);
+                    collect(applicand, path, redexes);
+                    path.pop();
+                    path.push(Direction::Argument);
+                    collect(argument, path, redexes);
+                    path.pop();
+                }
             }
-            _ => unimplemented!()
         }
+
+        let mut redexes = vec![];
+        collect(self, &mut vec![], &mut redexes);
+        redexes
+    }
+
+    /// Contracts the β-redex at `path`, or returns `None` if there is no
+    /// redex there.
+    pub fn contract_at(&self, path: &[Direction]) -> Option<Term> {
+        let (direction, rest) = match path.split_first() {
+            Some((&direction, rest)) => (direction, rest),
+            None => return match *self {
+                Term::Application { ref applicand, ref argument } => match **applicand {
+                    Term::Lambda { ref body, .. } => Some(Term::contract((**body).clone(), (**argument).clone())),
+                    _ => None,
+                },
+                _ => None,
+            },
+        };
+
+        match *self {
+            Term::Lambda { ref body, ref hint } if direction == Direction::Body => {
+                body.contract_at(rest).map(|body| Term::named_lambda(hint.clone(), body))
+            }
+            Term::Application { ref applicand, ref argument } if direction == Direction::Applicand => {
+                applicand.contract_at(rest).map(|t| Term::apply(t, (**argument).clone()))
+            }
+            Term::Application { ref applicand, ref argument } if direction == Direction::Argument => {
+                argument.contract_at(rest).map(|t| Term::apply((**applicand).clone(), t))
+            }
+            _ => None,
+        }
+    }
+
+    /// The subterm at `path`, if there is one.
+    pub fn subterm(&self, path: &[Direction]) -> Option<&Term> {
+        path.iter().try_fold(self, |term, &direction| match *term {
+            Term::Lambda { ref body, .. } if direction == Direction::Body => Some(&**body),
+            Term::Application { ref applicand, .. } if direction == Direction::Applicand => Some(&**applicand),
+            Term::Application { ref argument, .. } if direction == Direction::Argument => Some(&**argument),
+            _ => None,
+        })
     }
 
-    fn fmt(&self, f: &mut fmt::Formatter, depth: u32, symbols: &mut Vec<String>) -> fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter, depth: u32, symbols: &mut Scope) -> fmt::Result {
         use self::Term::*;
 
         match *self {
@@ -228,6 +734,14 @@ impl Term {
                     return write!(f, "{}", name);
                 }
             }
+            Application { .. } if self.power().is_some() => {
+                let (function, n, operand) = self.power().unwrap();
+                write!(f, "(")?;
+                function.fmt(f, depth, symbols)?;
+                write!(f, "^{} ", n)?;
+                operand.fmt(f, depth, symbols)?;
+                return write!(f, ")");
+            }
             Application { ref applicand, ref argument } => {
                 write!(f, "(")?;
                 applicand.fmt(f, depth, symbols)?;
@@ -235,30 +749,362 @@ impl Term {
                 argument.fmt(f, depth, symbols)?;
                 return write!(f, ")");
             }
-            Lambda { ref body } => {
-                let name = format!("x{}", depth);
-                write!(f, "(λ{}.", name)?;
+            Lambda { ref body, ref hint } => {
+                if self.fmt_literal(f, depth, symbols)? {
+                    return Ok(());
+                }
+
                 assert_eq!(symbols.len(), depth as usize);
+                let name = symbols.binder_name(hint, body);
+                write!(f, "(λ{}", name)?;
                 symbols.push(name);
 
-                body.fmt(f, depth + 1, symbols)?;
-                symbols.pop();
+                // the alternate form `{:#}` collects nested lambdas into
+                // one binder list
+                let mut body = body;
+                let mut binders = 1;
+                if f.alternate() {
+                    while let Lambda { body: ref inner, ref hint } = **body {
+                        let name = symbols.binder_name(hint, inner);
+                        write!(f, " {}", name)?;
+                        symbols.push(name);
+                        body = inner;
+                        binders += 1;
+                    }
+                }
+
+                write!(f, ".")?;
+                body.fmt(f, depth + binders, symbols)?;
+                for _ in 0..binders {
+                    symbols.pop();
+                }
                 return write!(f, ")");
             }
         }
     }
 }
 
+impl Term {
+    /// Performs a single η-reduction step, contracting `λx.M x` to `M` where
+    /// `x` does not occur in `M`. The leftmost-outermost such redex is
+    /// contracted, searching only the parts of the term `strategy` reduces.
+    pub fn eta_reduce(self, strategy: Strategy) -> EvalResult {
+        let mut path = vec![];
+        self.eta_reduce_along(strategy, &mut path).at(path)
+    }
+
+    fn eta_reduce_along(self, strategy: Strategy, path: &mut Path) -> Step {
+        use self::Step::*;
+
+        fn within(path: &mut Path, direction: Direction, term: Term, strategy: Strategy) -> Step {
+            path.push(direction);
+            let result = term.eta_reduce_along(strategy, path);
+            if let Normal(_) = result {
+                path.pop();
+            }
+            result
+        }
+
+        match self {
+            v @ Term::Variable { .. } => Normal(v),
+            Term::Lambda { body, hint } => {
+                if body.is_eta_redex_body() {
+                    return match *body {
+                        Term::Application { applicand, .. } => {
+                            let mut contractum = *applicand;
+                            contractum.rebind_free(-1, 0);
+                            Reduced(contractum)
+                        }
+                        _ => unreachable!(),
+                    };
+                }
+
+                if strategy.reduces_under_lambda() {
+                    within(path, Direction::Body, *body, strategy).map(|body| Term::named_lambda(hint, body))
+                } else {
+                    Normal(Term::named_lambda(hint, *body))
+                }
+            }
+            Term::Application { applicand, argument } => {
+                match within(path, Direction::Applicand, *applicand, strategy) {
+                    Reduced(t) => Reduced(Term::apply(t, *argument)),
+                    Normal(applicand) => {
+                        if strategy.reduces_arguments() || strategy.evaluates_arguments_first() {
+                            within(path, Direction::Argument, *argument, strategy).map(|t| Term::apply(applicand, t))
+                        } else {
+                            Normal(Term::apply(applicand, *argument))
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether `self` is the body `M x` of an η-redex `λx.M x`.
+    fn is_eta_redex_body(&self) -> bool {
+        match *self {
+            Term::Application { ref applicand, ref argument } => {
+                **argument == Term::variable(Name::bound(1)) && !applicand.refers_to(1)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether `self` refers to the binder `depth` lambdas outside of it.
+    fn refers_to(&self, depth: u32) -> bool {
+        match *self {
+            Term::Variable { ref name } => name.bound_at(depth),
+            Term::Lambda { ref body, .. } => body.refers_to(depth + 1),
+            Term::Application { ref applicand, ref argument } => {
+                applicand.refers_to(depth) || argument.refers_to(depth)
+            }
+        }
+    }
+
+    /// Returns `λx.self x`.
+    pub fn eta_expand(mut self) -> Term {
+        self.rebind_free(1, 0);
+        Term::lambda(Term::apply(self, Term::variable(Name::bound(1))))
+    }
+
+    /// Decides whether `self` and `other` are β-equal, or βη-equal with
+    /// `eta`. Both terms are reduced in normal order, a step at a time each,
+    /// and are equal as soon as one reaches a term the other has passed
+    /// through; terms are compared up to α-equivalence. Each side takes at
+    /// most `max_reductions` steps, and the check stops once `cancellation`
+    /// is cancelled.
+    pub fn equivalent(&self, other: &Term, max_reductions: usize, eta: bool, cancellation: &CancellationToken) -> Equivalence {
+        use self::Outcome::*;
+
+        if self == other {
+            return Equivalence::Equal;
+        }
+
+        let mut left = Reduction::new(self);
+        let mut right = Reduction::new(other);
+
+        loop {
+            if cancellation.is_cancelled() {
+                return Equivalence::Unknown(Undecided::Interrupted);
+            }
+
+            for &side in &[Side::Left, Side::Right] {
+                let (this, other) = match side {
+                    Side::Left => (&mut left, &right),
+                    Side::Right => (&mut right, &left),
+                };
+                if this.outcome.is_some() {
+                    continue;
+                }
+                if this.steps >= max_reductions {
+                    this.outcome = Some(TooManyReductions);
+                    continue;
+                }
+
+                match this.term.clone().normalize_step(eta) {
+                    EvalResult::NormalForm(_) => this.outcome = Some(NormalForm),
+                    EvalResult::Reduced { term: t, .. } => {
+                        if other.seen.contains(&t) {
+                            return Equivalence::Equal;
+                        }
+                        if !this.seen.insert(t.clone()) {
+                            this.outcome = Some(Cycle);
+                        }
+                        this.steps += 1;
+                        this.term = t;
+                    }
+                }
+            }
+
+            return match (left.outcome, right.outcome) {
+                (Some(NormalForm), Some(NormalForm)) => {
+                    Equivalence::Different(Difference::NormalForms { left: left.term, right: right.term })
+                }
+                (Some(NormalForm), Some(Cycle)) => {
+                    Equivalence::Different(Difference::NormalFormOnly { side: Side::Left })
+                }
+                (Some(Cycle), Some(NormalForm)) => {
+                    Equivalence::Different(Difference::NormalFormOnly { side: Side::Right })
+                }
+                (Some(Cycle), Some(Cycle)) => Equivalence::Unknown(Undecided::NoNormalForms),
+                (Some(TooManyReductions), Some(_)) => {
+                    Equivalence::Unknown(Undecided::TooManyReductions { side: Side::Left })
+                }
+                (Some(_), Some(TooManyReductions)) => {
+                    Equivalence::Unknown(Undecided::TooManyReductions { side: Side::Right })
+                }
+                _ => continue,
+            };
+        }
+    }
+
+    /// A normal order step, which is an η-reduction with `eta` once no
+    /// β-redex is left.
+    fn normalize_step(self, eta: bool) -> EvalResult {
+        match self.reduce(Strategy::NormalOrder) {
+            EvalResult::NormalForm(t) => {
+                if eta { t.eta_reduce(Strategy::NormalOrder) } else { EvalResult::NormalForm(t) }
+            }
+            reducible => reducible,
+        }
+    }
+
+    /// Splits a chain `f (f (... x))` of at least three applications of the
+    /// same variable `f` into `f`, their number and `x`, to be printed as
+    /// `f^n x`. Longer chains than `MAX_POWER` are split into several
+    /// powers, so `x` may be a chain itself.
+    pub fn power(&self) -> Option<(&Term, u32, &Term)> {
+        let function = match *self {
+            Term::Application { ref applicand, .. } => match **applicand {
+                Term::Variable { .. } => &**applicand,
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        let mut n = 0;
+        let mut operand = self;
+        while let Term::Application { ref applicand, ref argument } = *operand {
+            if **applicand != *function || n == MAX_POWER {
+                break;
+            }
+            n += 1;
+            operand = argument;
+        }
+
+        if n >= 3 { Some((function, n, operand)) } else { None }
+    }
+
+    /// Collects the printed names of the variables in `self` which are bound
+    /// outside of the innermost `binders` lambdas, or free.
+    fn visible_names(&self, binders: u32, symbols: &[String], names: &mut HashSet<String>) {
+        match *self {
+            Term::Variable { name: Name::Free { ref name } } => {
+                names.insert(name.clone());
+            }
+            Term::Variable { name: Name::Bound { depth } } if depth > binders => {
+                let index = (depth - binders) as usize;
+                if index <= symbols.len() {
+                    names.insert(symbols[symbols.len() - index].clone());
+                }
+            }
+            Term::Variable { .. } => {}
+            Term::Lambda { ref body, .. } => body.visible_names(binders + 1, symbols, names),
+            Term::Application { ref applicand, ref argument } => {
+                applicand.visible_names(binders, symbols, names);
+                argument.visible_names(binders, symbols, names);
+            }
+        }
+    }
+
+    /// Prints lists and tuples in their bracketed notation. Returns whether
+    /// the term is one of them.
+    fn fmt_literal(&self, f: &mut fmt::Formatter, depth: u32, symbols: &mut Scope) -> Result<bool, fmt::Error> {
+        let literal = match encoding::decode_literal(self) {
+            Some(literal) => literal,
+            None => return Ok(false),
+        };
+
+        let (open, close) = literal.delimiters();
+        write!(f, "{}", open)?;
+        for (i, element) in literal.elements().iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            element.fmt(f, depth, symbols)?;
+        }
+        write!(f, "{}", close)?;
+        Ok(true)
+    }
+}
+
+/// Prints the term fully parenthesized, in a form the parser reads back as
+/// the same term as long as its free variables are identifiers. Variables
+/// bound outside of the term are printed as de Bruijn indices `↑n`.
 impl fmt::Display for Term {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut symbols = vec![];
+        let mut symbols = Scope::new(self);
         self.fmt(f, 0, &mut symbols)
     }
 }
 
+/// The printed names of the binders around a subterm, the innermost last.
+/// Alongside, it counts the names a new binder might capture: these and the
+/// free variables of the whole term.
+pub struct Scope {
+    names: Vec<String>,
+    taken: HashMap<String, usize>,
+}
+
+impl Scope {
+    /// The scope at the top of `term`.
+    pub fn new(term: &Term) -> Scope {
+        let taken = term.free_variables().into_iter().map(|name| (name, 1)).collect();
+        Scope { names: vec![], taken }
+    }
+
+    pub fn push(&mut self, name: String) {
+        *self.taken.entry(name.clone()).or_default() += 1;
+        self.names.push(name);
+    }
+
+    pub fn pop(&mut self) {
+        if let Some(name) = self.names.pop() {
+            let count = self.taken.get_mut(&name).expect("pushed names are counted");
+            *count -= 1;
+            if *count == 0 {
+                self.taken.remove(&name);
+            }
+        }
+    }
+
+    /// Chooses the printed name of a binder with the given `hint` and
+    /// `body`. The hint is used unless it would capture a variable of the
+    /// body; then, and for binders without a hint, a numbered name is made
+    /// up.
+    pub fn binder_name(&self, hint: &Hint, body: &Term) -> String {
+        let name = match hint.name() {
+            Some(name) => name.to_owned(),
+            None => format!("x{}", self.names.len()),
+        };
+        // every name visible in the body is taken, so only a taken name has
+        // to be checked against the body
+        if !self.taken.contains_key(&name) {
+            return name;
+        }
+
+        let mut visible = HashSet::new();
+        body.visible_names(1, &self.names, &mut visible);
+        match hint.name() {
+            Some(hint) if !visible.contains(hint) => name,
+            Some(hint) => (1..).map(|i| format!("{}{}", hint, i)).find(|n| !visible.contains(n)).unwrap(),
+            None => (self.names.len()..).map(|i| format!("x{}", i)).find(|n| !visible.contains(n)).unwrap(),
+        }
+    }
+}
+
+impl Deref for Scope {
+    type Target = [String];
+
+    fn deref(&self) -> &[String] {
+        &self.names
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use ::lexer::Token;
+    use ::parser::parse;
+    use ::random::TermGenerator;
+    use ::runtime::Statement;
+
+    fn parse_term(input: &str) -> Term {
+        match parse(&Token::parse_all(input).unwrap()) {
+            Ok(Statement::Expression(term)) => term,
+            result => panic!("{:?}", result),
+        }
+    }
 
     #[test]
     fn test_reduction_simple() {
@@ -269,7 +1115,7 @@ mod test {
 
         let result = term.reduce(Strategy::NormalOrder);
         assert_eq!(
-            EvalResult::PossiblyReducible(Term::lambda(Term::variable(Name::bound(1)))),
+            EvalResult::Reduced { term: Term::lambda(Term::variable(Name::bound(1))), redex: vec![Direction::Body] },
             result
         );
 
@@ -299,8 +1145,8 @@ mod test {
 
         let result = term.reduce(Strategy::NormalOrder);
         assert_eq!(
-            EvalResult::PossiblyReducible(
-                Term::lambda(Term::lambda(
+            EvalResult::Reduced {
+                term: Term::lambda(Term::lambda(
                     Term::apply(
                         Term::apply(
                             Term::lambda(Term::lambda(
@@ -310,33 +1156,36 @@ mod test {
                         ),
                         Term::variable(Name::bound(1)),
                     )
-                ))
-             ),
-             result
+                )),
+                redex: vec![],
+            },
+            result
         );
 
         let result = result.unwrap().reduce(Strategy::NormalOrder);
         assert_eq!(
-            EvalResult::PossiblyReducible(
-                Term::lambda(Term::lambda(
+            EvalResult::Reduced {
+                term: Term::lambda(Term::lambda(
                     Term::apply(
                         Term::lambda(
                             Term::variable(Name::bound(3)),
                         ),
                         Term::variable(Name::bound(1)),
                     )
-                ))
-            ),
+                )),
+                redex: vec![Direction::Body, Direction::Body, Direction::Applicand],
+            },
             result
         );
 
         let result = result.unwrap().reduce(Strategy::NormalOrder);
         assert_eq!(
-            EvalResult::PossiblyReducible(
-                Term::lambda(Term::lambda(
+            EvalResult::Reduced {
+                term: Term::lambda(Term::lambda(
                     Term::variable(Name::bound(2)),
-                ))
-            ),
+                )),
+                redex: vec![Direction::Body, Direction::Body],
+            },
             result
         );
 
@@ -351,6 +1200,306 @@ mod test {
         );
     }
 
+    fn i() -> Term {
+        Term::lambda(Term::variable(Name::bound(1)))
+    }
+
+    fn k() -> Term {
+        Term::lambda(Term::lambda(Term::variable(Name::bound(2))))
+    }
+
+    fn omega() -> Term {
+        let w = Term::lambda(Term::apply(
+            Term::variable(Name::bound(1)),
+            Term::variable(Name::bound(1)),
+        ));
+        Term::apply(w.clone(), w)
+    }
+
+    fn free(name: &str) -> Term {
+        Term::variable(Name::free(name.into()))
+    }
+
+    fn normalize(mut term: Term, strategy: Strategy, limit: usize) -> Option<Term> {
+        for _ in 0..limit {
+            match term.reduce(strategy) {
+                EvalResult::NormalForm(t) => return Some(t),
+                EvalResult::Reduced { term: t, .. } => term = t,
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn test_strategies_k_i_omega() {
+        let term = Term::apply(Term::apply(k(), i()), omega());
+
+        for &strategy in &[Strategy::NormalOrder, Strategy::CallByName,
+                           Strategy::HeadNormal, Strategy::WeakHeadNormal] {
+            assert_eq!(Some(i()), normalize(term.clone(), strategy, 10), "{}", strategy);
+        }
+
+        for &strategy in &[Strategy::ApplicativeOrder, Strategy::CallByValue] {
+            assert_eq!(None, normalize(term.clone(), strategy, 100), "{}", strategy);
+        }
+    }
+
+    #[test]
+    fn test_strategies_under_lambda() {
+        // (λx.(I x))
+        let term = Term::lambda(Term::apply(i(), Term::variable(Name::bound(1))));
+
+        for &strategy in &[Strategy::NormalOrder, Strategy::ApplicativeOrder, Strategy::HeadNormal] {
+            assert_eq!(Some(i()), normalize(term.clone(), strategy, 10), "{}", strategy);
+        }
+
+        for &strategy in &[Strategy::CallByValue, Strategy::CallByName, Strategy::WeakHeadNormal] {
+            assert_eq!(
+                EvalResult::NormalForm(term.clone()),
+                term.clone().reduce(strategy),
+                "{}", strategy
+            );
+        }
+    }
+
+    #[test]
+    fn test_strategies_stuck_application() {
+        // (x (I z))
+        let term = Term::apply(free("x"), Term::apply(i(), free("z")));
+        let reduced = Term::apply(free("x"), free("z"));
+
+        for &strategy in &[Strategy::NormalOrder, Strategy::ApplicativeOrder, Strategy::CallByValue] {
+            assert_eq!(Some(reduced.clone()), normalize(term.clone(), strategy, 10), "{}", strategy);
+        }
+
+        for &strategy in &[Strategy::CallByName, Strategy::HeadNormal, Strategy::WeakHeadNormal] {
+            assert_eq!(EvalResult::NormalForm(term.clone()), term.clone().reduce(strategy), "{}", strategy);
+        }
+    }
+
+    #[test]
+    fn test_call_by_name_stops_at_weak_head_normal_form() {
+        // (λx.((λy.y) x)) is a lambda, so call-by-name leaves its body alone
+        let term = Term::lambda(Term::apply(i(), Term::variable(Name::bound(1))));
+        assert_eq!(Some(i()), normalize(term.clone(), Strategy::NormalOrder, 10));
+        assert_eq!(Some(term.clone()), normalize(term, Strategy::CallByName, 10));
+
+        // ((λx.x) f ((λy.y) z)) is reduced only as far as its head
+        let term = Term::apply(Term::apply(i(), free("f")), Term::apply(i(), free("z")));
+        assert_eq!(Some(Term::apply(free("f"), free("z"))), normalize(term.clone(), Strategy::NormalOrder, 10));
+        assert_eq!(
+            Some(Term::apply(free("f"), Term::apply(i(), free("z")))),
+            normalize(term, Strategy::CallByName, 10),
+        );
+    }
+
+    #[test]
+    fn test_strategies_argument_first() {
+        // ((λx.y) (I z))
+        let term = Term::apply(Term::lambda(free("y")), Term::apply(i(), free("z")));
+
+        for &strategy in &[Strategy::ApplicativeOrder, Strategy::CallByValue] {
+            assert_eq!(
+                EvalResult::Reduced {
+                    term: Term::apply(Term::lambda(free("y")), free("z")),
+                    redex: vec![Direction::Argument],
+                },
+                term.clone().reduce(strategy),
+                "{}", strategy
+            );
+        }
+
+        for &strategy in &[Strategy::NormalOrder, Strategy::CallByName,
+                           Strategy::HeadNormal, Strategy::WeakHeadNormal] {
+            assert_eq!(
+                EvalResult::Reduced { term: free("y"), redex: vec![] },
+                term.clone().reduce(strategy),
+                "{}", strategy
+            );
+        }
+    }
+
+    #[test]
+    fn test_display_collapsed_binders() {
+        let term = Term::lambda(Term::lambda(Term::apply(
+            Term::variable(Name::bound(2)),
+            Term::lambda(Term::lambda(Term::variable(Name::bound(1)))),
+        )));
+
+        assert_eq!("(λx0.(λx1.(x0 (λx2.(λx3.x3)))))", format!("{}", term));
+        assert_eq!("(λx0 x1.(x0 (λx2 x3.x3)))", format!("{:#}", term));
+    }
+
+    #[test]
+    fn test_display_lists_and_tuples() {
+        let x = Term::variable(Name::bound(1));
+        let list = encoding::encode_list(vec![Term::variable(Name::free("a".into())), x.clone()]);
+        let tuple = encoding::encode_tuple(vec![list, Term::lambda(x.clone())]);
+
+        assert_eq!("(λx0.<[a, x0], (λx1.x1)>)", format!("{}", Term::lambda(tuple)));
+        assert_eq!("(λc.(λn.n))", format!("{}", encoding::encode_list(vec![])));
+        assert_eq!("(λs.(s a))", format!("{}", encoding::encode_tuple(vec![Term::variable(Name::free("a".into()))])));
+    }
+
+    #[test]
+    fn test_display_powers() {
+        let f = || Term::variable(Name::free("f".into()));
+        let x = Term::variable(Name::free("x".into()));
+        let chain = |n| (0..n).fold(x.clone(), |term, _| Term::apply(f(), term));
+
+        assert_eq!("(f (f x))", chain(2).to_string());
+        assert_eq!("(f^120 x)", chain(120).to_string());
+        assert_eq!("(λf.(λx.(f^5 x)))", encoding::NumeralEncoding::Church.encode(5).unwrap().to_string());
+    }
+
+    #[test]
+    fn test_display_binder_names() {
+        assert_eq!("(λf.(λx.(f x)))", parse_term("(Lf.(Lx.(f x)))").to_string());
+        assert_eq!("(λx.((λx.x) x))", parse_term("Lx.(Lx.x) x").to_string());
+        assert_eq!("((λx.x) x)", parse_term("(Lx.x) x").to_string());
+
+        // binders are renamed where their name would capture a variable
+        let term = parse_term("(Lf x.f x) x").reduce(Strategy::NormalOrder).unwrap();
+        assert_eq!("(λx1.(x x1))", term.to_string());
+        let term = parse_term("Ly.(Lx y.x) y").reduce(Strategy::NormalOrder).unwrap();
+        assert_eq!("(λy.(λy1.y))", term.to_string());
+        assert_eq!("(λy x1.(x y))", format!("{:#}", parse_term("(Lz.Ly x.z y) x").reduce(Strategy::NormalOrder).unwrap()));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        fn unnamed(term: &Term) -> Term {
+            match *term {
+                Term::Variable { .. } => term.clone(),
+                Term::Lambda { ref body, .. } => Term::lambda(unnamed(body)),
+                Term::Application { ref applicand, ref argument } => Term::apply(unnamed(applicand), unnamed(argument)),
+            }
+        }
+
+        // parsing a printed term gives the same term, which prints the same,
+        // binder names included
+        fn assert_round_trip(term: &Term) {
+            let printed = term.to_string();
+            let parsed = parse_term(&printed);
+            assert_eq!(*term, parsed, "{}", printed);
+            assert_eq!(printed, parsed.to_string());
+        }
+
+        let mut generator = TermGenerator::new(3);
+        for _ in 0..500 {
+            let term = generator.term(25);
+            assert_round_trip(&term);
+            assert_round_trip(&unnamed(&term));
+
+            // reduction substitutes under binders, which may have to be renamed
+            for &strategy in Strategy::ALL.iter() {
+                assert_round_trip(&term.clone().reduce(strategy).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_eta() {
+        use self::Direction::*;
+        use self::EvalResult::*;
+
+        assert_eq!(Reduced { term: parse_term("f"), redex: vec![] }, parse_term("Lx.f x").eta_reduce(Strategy::NormalOrder));
+        assert_eq!(
+            Reduced { term: parse_term("Ly.y"), redex: vec![Body] },
+            parse_term("Ly x.y x").eta_reduce(Strategy::NormalOrder)
+        );
+        assert_eq!(NormalForm(parse_term("Lx.x x")), parse_term("Lx.x x").eta_reduce(Strategy::NormalOrder));
+        assert_eq!(NormalForm(parse_term("Lx.f (x y)")), parse_term("Lx.f (x y)").eta_reduce(Strategy::NormalOrder));
+
+        // only the head is searched by the weak strategies
+        let term = parse_term("g (Lx.f x)");
+        assert_eq!(
+            Reduced { term: parse_term("g f"), redex: vec![Argument] },
+            term.clone().eta_reduce(Strategy::NormalOrder)
+        );
+        assert_eq!(NormalForm(term.clone()), term.eta_reduce(Strategy::HeadNormal));
+
+        assert_eq!(parse_term("Lx.f x"), parse_term("f").eta_expand());
+        assert_eq!(parse_term("Lx.(Ly.y) x"), parse_term("Ly.y").eta_expand());
+        assert_eq!(parse_term("Ly x.x y"), parse_term("Ly x.x y").eta_expand().eta_reduce(Strategy::NormalOrder).unwrap());
+        for &eta in Eta::ALL.iter() {
+            assert_eq!(Ok(eta), eta.name().parse());
+        }
+    }
+
+    #[test]
+    fn test_redexes() {
+        use self::Direction::*;
+
+        let term = parse_term("Lx.(Ly.y) ((Lz.z) x) ((Lw.w) a)");
+        let redexes = vec![
+            vec![Body, Applicand],
+            vec![Body, Applicand, Argument],
+            vec![Body, Argument],
+        ];
+        assert_eq!(redexes, term.redexes());
+        assert_eq!(Some(parse_term("Lx.(Lz.z) x ((Lw.w) a)")), term.contract_at(&redexes[0]));
+        assert_eq!(Some(parse_term("Lx.(Ly.y) x ((Lw.w) a)")), term.contract_at(&redexes[1]));
+        assert_eq!(Some(parse_term("Lx.(Ly.y) ((Lz.z) x) a")), term.contract_at(&redexes[2]));
+        assert_eq!(None, term.contract_at(&[Body]));
+        assert_eq!(None, term.contract_at(&[Applicand]));
+        assert!(parse_term("f (g x)").redexes().is_empty());
+
+        // the strategies contract one of the redexes
+        let mut generator = TermGenerator::new(4);
+        for _ in 0..200 {
+            let term = generator.term(12);
+            for &strategy in Strategy::ALL.iter() {
+                if let EvalResult::Reduced { term: reduct, redex } = term.clone().reduce(strategy) {
+                    assert!(term.redexes().contains(&redex), "{} {}", term, strategy);
+                    assert_eq!(Some(reduct), term.contract_at(&redex), "{} {}", term, strategy);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_equivalent() {
+        let cancellation = CancellationToken::new();
+        let equivalent = |left: &str, right: &str, eta| {
+            parse_term(left).equivalent(&parse_term(right), 100, eta, &cancellation)
+        };
+
+        assert_eq!(Equivalence::Equal, equivalent("Lx.x", "Ly.y", false));
+        assert_eq!(Equivalence::Equal, equivalent("(Lx y.x) a b", "(Lx.x) a", false));
+        assert_eq!(Equivalence::Equal, equivalent("(Lx.x x) (Lx.x x)", "(Ly.y) ((Lx.x x) (Lx.x x))", false));
+        assert_eq!(Equivalence::Equal, equivalent("Lx.f x", "f", true));
+        assert_eq!(
+            Equivalence::Different(Difference::NormalForms { left: parse_term("Lx.f x"), right: parse_term("f") }),
+            equivalent("Lx.f x", "f", false)
+        );
+        assert_eq!(
+            Equivalence::Different(Difference::NormalFormOnly { side: Side::Right }),
+            equivalent("(Lx.x x) (Lx.x x)", "a", false)
+        );
+        assert_eq!(
+            Equivalence::Unknown(Undecided::NoNormalForms),
+            equivalent("(Lx.x x) (Lx.x x)", "(Lx.x x) (Lx.x x) a", false)
+        );
+        assert_eq!(
+            Equivalence::Unknown(Undecided::TooManyReductions { side: Side::Left }),
+            equivalent("(Lx.x x x) (Lx.x x x)", "a", false)
+        );
+        cancellation.cancel();
+        assert_eq!(
+            Equivalence::Unknown(Undecided::Interrupted),
+            equivalent("(Lx.x x) (Lx.x x)", "a", false)
+        );
+    }
+
+    #[test]
+    fn test_strategy_names() {
+        for &strategy in Strategy::ALL.iter() {
+            assert_eq!(Ok(strategy), strategy.name().parse());
+        }
+        assert!("lazy".parse::<Strategy>().is_err());
+    }
+
     #[test]
     fn test_bind_free_dummy() {
         let lambda = Term::lambda(Term::variable(Name::free("a".into())));
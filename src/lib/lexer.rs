@@ -1,13 +1,32 @@
+use std::error;
 use std::fmt;
+use std::iter::Peekable;
 
 #[derive(Debug, PartialEq)]
 pub enum Token {
     ParenOpen,
     ParenClose,
+    BracketOpen,
+    BracketClose,
+    AngleOpen,
+    AngleClose,
+    Comma,
     Lambda,
     Dot,
     Identifier(String),
+    /// A `#n` literal.
+    Numeral(u32),
+    /// The `^n` in `f^n x`.
+    Power(u32),
+    /// A de Bruijn index `↑n`, referring to the n-th enclosing binder.
+    Index(u32),
+    /// A `"..."` literal.
+    Text(String),
+    /// A `'c'` literal.
+    Character(char),
     Let,
+    In,
+    Where,
     DefineReduce,
     DefineSuspend,
 }
@@ -19,81 +38,317 @@ impl fmt::Display for Token {
         match *self {
             ParenOpen => write!(f, "("),
             ParenClose => write!(f, ")"),
+            BracketOpen => write!(f, "["),
+            BracketClose => write!(f, "]"),
+            AngleOpen => write!(f, "<"),
+            AngleClose => write!(f, ">"),
+            Comma => write!(f, ","),
             Lambda => write!(f, "λ"),
             Dot => write!(f, "."),
             Identifier(ref name) => write!(f, "{}", name),
+            Numeral(n) => write!(f, "#{}", n),
+            Power(n) => write!(f, "^{}", n),
+            Index(n) => write!(f, "↑{}", n),
+            Text(ref text) => write_quoted(f, text, '"'),
+            Character(c) => write_quoted(f, c.encode_utf8(&mut [0; 4]), '\''),
             Let => write!(f, "let"),
+            In => write!(f, "in"),
+            Where => write!(f, "where"),
             DefineReduce => write!(f, "="),
             DefineSuspend => write!(f, ":="),
         }
     }
 }
 
+/// Writes `text` between `quote`s, using only the escapes the lexer reads
+/// back. Any other character, control characters included, is written as it
+/// is.
+fn write_quoted(f: &mut fmt::Formatter, text: &str, quote: char) -> fmt::Result {
+    write!(f, "{}", quote)?;
+    for c in text.chars() {
+        match c {
+            '\n' => write!(f, "\\n")?,
+            '\t' => write!(f, "\\t")?,
+            '\\' => write!(f, "\\\\")?,
+            c if c == quote => write!(f, "\\{}", c)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "{}", quote)
+}
+
+/// A byte range in the source text.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    /// The 1-based line of the start of the span.
+    pub fn line(&self, source: &str) -> usize {
+        source[..self.start].matches('\n').count() + 1
+    }
+
+    /// The 1-based column of the start of the span within its line, counted
+    /// in characters.
+    pub fn column(&self, source: &str) -> usize {
+        source[self.line_start(source)..self.start].chars().count() + 1
+    }
+
+    /// The number of characters covered by the span on its first line.
+    pub fn width(&self, source: &str) -> usize {
+        source[self.start..self.end].split('\n').next().unwrap().chars().count()
+    }
+
+    /// The text of the line the span starts on.
+    pub fn line_text<'a>(&self, source: &'a str) -> &'a str {
+        let start = self.line_start(source);
+        let end = source[self.start..].find('\n').map_or(source.len(), |i| self.start + i);
+        &source[start..end]
+    }
+
+    fn line_start(&self, source: &str) -> usize {
+        source[..self.start].rfind('\n').map_or(0, |i| i + 1)
+    }
+}
+
+/// A token together with its position in the source text.
+#[derive(Debug, PartialEq)]
+pub struct Lexeme {
+    pub token: Token,
+    pub span: Span,
+}
+
+impl fmt::Display for Lexeme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.token)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
-pub struct ParseTokenError(pub String);
+pub struct ParseTokenError {
+    pub message: String,
+    pub span: Span,
+    /// The input ended inside a block comment, so appending more input
+    /// could make it valid.
+    pub incomplete: bool,
+}
+
+impl ParseTokenError {
+    fn invalid(start: usize, c: char) -> ParseTokenError {
+        ParseTokenError {
+            message: format!("Invalid token: {}", c),
+            span: Span::new(start, start + c.len_utf8()),
+            incomplete: false,
+        }
+    }
+}
+
+impl fmt::Display for ParseTokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl error::Error for ParseTokenError {}
 
 impl Token {
-    pub fn parse_all(s: &str) -> Result<Vec<Token>, ParseTokenError> {
+    pub fn parse_all(s: &str) -> Result<Vec<Lexeme>, ParseTokenError> {
         use self::Token::*;
 
         let mut tokens = vec![];
-        let mut iterator = s.chars().peekable(); 
+        let mut iterator = s.char_indices().peekable();
 
-        while let Some(c) = iterator.next() {
+        while let Some((start, c)) = iterator.next() {
             if c.is_whitespace() { continue; }
-            match c {
-                '(' => tokens.push(ParenOpen),
-                ')' => tokens.push(ParenClose),
-                'λ' | 'L' => tokens.push(Lambda),
-                '.' => tokens.push(Dot),
-                '=' => tokens.push(DefineReduce),
+            let token = match c {
+                '(' => ParenOpen,
+                ')' => ParenClose,
+                '[' => BracketOpen,
+                ']' => BracketClose,
+                '<' => AngleOpen,
+                '>' => AngleClose,
+                ',' => Comma,
+                'λ' | 'L' | '\\' => Lambda,
+                '.' => Dot,
+                '=' => DefineReduce,
+                '#' => Numeral(number(&mut iterator, start, c, "Numeral")?),
+                '^' => Power(number(&mut iterator, start, c, "Exponent")?),
+                '↑' => match number(&mut iterator, start, c, "Index")? {
+                    0 => return Err(ParseTokenError::invalid(start, c)),
+                    n => Index(n),
+                },
+                '"' => {
+                    let mut text = String::new();
+                    loop {
+                        match iterator.next() {
+                            Some((_, '"')) => break,
+                            Some((position, '\\')) => text.push(escaped(&mut iterator, position)?),
+                            Some((_, '\n')) | None => return Err(ParseTokenError {
+                                message: "Unterminated string literal".into(),
+                                span: Span::new(start, start + 1),
+                                incomplete: false,
+                            }),
+                            Some((_, c)) => text.push(c),
+                        }
+                    }
+                    Text(text)
+                }
+                '\'' => {
+                    let character = match iterator.next() {
+                        Some((position, '\\')) => escaped(&mut iterator, position)?,
+                        Some((_, c)) if c != '\'' && c != '\n' => c,
+                        _ => return Err(ParseTokenError::invalid(start, c)),
+                    };
+                    match iterator.next() {
+                        Some((_, '\'')) => Character(character),
+                        _ => return Err(ParseTokenError {
+                            message: "Unterminated character literal".into(),
+                            span: Span::new(start, start + 1),
+                            incomplete: false,
+                        }),
+                    }
+                }
+                '-' => {
+                    // line comment, up to the end of the line
+                    match iterator.next() {
+                        Some((_, '-')) => {
+                            for (_, c) in iterator.by_ref() {
+                                if c == '\n' { break; }
+                            }
+                            continue;
+                        }
+                        _ => return Err(ParseTokenError::invalid(start, c)),
+                    }
+                }
+                '{' => {
+                    // block comment, which may nest
+                    match iterator.next() {
+                        Some((_, '-')) => {
+                            let mut depth = 1;
+                            while depth > 0 {
+                                match iterator.next() {
+                                    Some((_, '{')) if iterator.peek().map(|&(_, c)| c) == Some('-') => {
+                                        iterator.next();
+                                        depth += 1;
+                                    }
+                                    Some((_, '-')) if iterator.peek().map(|&(_, c)| c) == Some('}') => {
+                                        iterator.next();
+                                        depth -= 1;
+                                    }
+                                    Some(_) => {}
+                                    None => return Err(ParseTokenError {
+                                        message: "Unterminated block comment".into(),
+                                        span: Span::new(start, start + 2),
+                                        incomplete: true,
+                                    }),
+                                }
+                            }
+                            continue;
+                        }
+                        _ => return Err(ParseTokenError::invalid(start, c)),
+                    }
+                }
                 ':' => {
                     match iterator.next() {
-                        Some('=') => tokens.push(DefineSuspend),
-                        _ => return Err(ParseTokenError(format!("Invalid token: :{}", c))),
+                        Some((_, '=')) => DefineSuspend,
+                        _ => return Err(ParseTokenError::invalid(start, c)),
                     }
                 },
                 c if c.is_ascii_alphanumeric()  => {
                     let mut word: String = String::new();
                     word.push(c);
 
-                    while let Some(&c) = iterator.peek() {
+                    while let Some(&(_, c)) = iterator.peek() {
                         if !c.is_ascii_alphanumeric() { break; }
-                        word.push(iterator.next().unwrap());
+                        word.push(iterator.next().unwrap().1);
                     }
 
-                    if word == "let" {
-                        tokens.push(Let);
-                    } else {
-                        tokens.push(Identifier(word));
+                    match word.as_str() {
+                        "let" => Let,
+                        "in" => In,
+                        "where" => Where,
+                        _ => Identifier(word),
                     }
                 }
-                _ => return Err(ParseTokenError(format!("Invalid token: {}", c))),
-            }
+                _ => return Err(ParseTokenError::invalid(start, c)),
+            };
+
+            let end = iterator.peek().map_or(s.len(), |&(end, _)| end);
+            tokens.push(Lexeme { token, span: Span::new(start, end) });
         }
 
         Ok(tokens)
     }
 }
 
+/// Reads the digits following the `prefix` character at `start`, as in `#42`.
+/// `kind` names the number in the error for too many digits.
+fn number<I: Iterator<Item = (usize, char)>>(iterator: &mut Peekable<I>, start: usize, prefix: char, kind: &str) -> Result<u32, ParseTokenError> {
+    let mut digits = String::new();
+    while let Some(&(_, c)) = iterator.peek() {
+        if !c.is_ascii_digit() { break; }
+        digits.push(iterator.next().unwrap().1);
+    }
+
+    if digits.is_empty() {
+        return Err(ParseTokenError::invalid(start, prefix));
+    }
+    digits.parse().map_err(|_| ParseTokenError {
+        message: format!("{} too large: {}{}", kind, prefix, digits),
+        span: Span::new(start, start + prefix.len_utf8() + digits.len()),
+        incomplete: false,
+    })
+}
+
+/// Reads the character after the backslash at `position` of an escape
+/// sequence in a string or character literal.
+fn escaped<I: Iterator<Item = (usize, char)>>(iterator: &mut I, position: usize) -> Result<char, ParseTokenError> {
+    match iterator.next() {
+        Some((_, 'n')) => Ok('\n'),
+        Some((_, 't')) => Ok('\t'),
+        Some((_, c @ '\\')) | Some((_, c @ '"')) | Some((_, c @ '\'')) => Ok(c),
+        Some((_, c)) => Err(ParseTokenError {
+            message: format!("Invalid escape sequence: \\{}", c),
+            span: Span::new(position, position + 1 + c.len_utf8()),
+            incomplete: false,
+        }),
+        None => Err(ParseTokenError::invalid(position, '\\')),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use self::Token::*;
 
+    fn parse_tokens(s: &str) -> Result<Vec<Token>, ParseTokenError> {
+        Token::parse_all(s).map(|lexemes| lexemes.into_iter().map(|l| l.token).collect())
+    }
+
     #[test]
     fn test_parse_tokens_correct() {
         assert_eq!(
             Ok(vec![ParenOpen, Lambda, Identifier("x".into()), Dot, Identifier("x".into()), ParenClose]),
-            Token::parse_all("  (Lx.  x  ) ")
+            parse_tokens("  (Lx.  x  ) ")
         );
     }
         
     #[test]
     fn test_parse_tokens_invalid() {
         assert_eq!(
-            Err(ParseTokenError("Invalid token: [".into())),
-            Token::parse_all("[Lx.x]"),
+            Err(ParseTokenError { message: "Invalid token: ;".into(), span: Span::new(0, 1), incomplete: false }),
+            parse_tokens(";Lx.x"),
+        );
+
+        assert_eq!(
+            Err(ParseTokenError { message: "Invalid token: :".into(), span: Span::new(5, 6), incomplete: false }),
+            parse_tokens("(Lx.x:)"),
         );
     }
 
@@ -101,7 +356,7 @@ mod test {
     fn test_parse_tokens_empty() {
         assert_eq!(
             Ok(vec![]),
-            Token::parse_all(" "),
+            parse_tokens(" "),
         );
     }
 
@@ -110,20 +365,106 @@ mod test {
         assert_eq!(
             Ok(vec![Let, Identifier("I".into()), DefineReduce,
                 ParenOpen, Lambda, Identifier("x".into()), Dot, Identifier("x".into()), ParenClose]),
-            Token::parse_all("let I = (Lx.x)"),
+            parse_tokens("let I = (Lx.x)"),
         );
 
         assert_eq!(
             Ok(vec![Let, Identifier("I".into()), DefineSuspend,
                 ParenOpen, Lambda, Identifier("x".into()), Dot, Identifier("x".into()), ParenClose]),
-            Token::parse_all("let I := (Lx.x)"),
+            parse_tokens("let I := (Lx.x)"),
+        );
+
+        assert_eq!(
+            Ok(vec![In, Where, Identifier("inside".into()), Identifier("whereas".into()), Identifier("lets".into())]),
+            parse_tokens("in where inside whereas lets"),
+        );
+    }
+
+    #[test]
+    fn test_parse_tokens_numerals() {
+        assert_eq!(Ok(vec![Identifier("succ".into()), Numeral(0), Numeral(12)]), parse_tokens("succ #0 #12"));
+        assert_eq!(
+            Err(ParseTokenError { message: "Invalid token: #".into(), span: Span::new(2, 3), incomplete: false }),
+            parse_tokens("a # 1"),
+        );
+        assert_eq!(
+            Err(ParseTokenError { message: "Numeral too large: #99999999999".into(), span: Span::new(0, 12), incomplete: false }),
+            parse_tokens("#99999999999"),
+        );
+        assert_eq!(
+            Ok(vec![Identifier("f".into()), Power(3), Identifier("x".into())]),
+            parse_tokens("f^3 x"),
+        );
+        assert_eq!(
+            Err(ParseTokenError { message: "Invalid token: ^".into(), span: Span::new(1, 2), incomplete: false }),
+            parse_tokens("f^x"),
+        );
+        assert_eq!(Ok(vec![Lambda, Dot, Index(12)]), parse_tokens("λ.↑12"));
+        assert_eq!(
+            Err(ParseTokenError { message: "Invalid token: ↑".into(), span: Span::new(0, 3), incomplete: false }),
+            parse_tokens("↑0"),
+        );
+    }
+
+    #[test]
+    fn test_parse_tokens_text() {
+        assert_eq!(
+            Ok(vec![Text("hi -- {-".into()), Character('c'), Text("".into()), Character('\\')]),
+            parse_tokens(r#""hi -- {-" 'c' "" '\\'"#),
+        );
+        assert_eq!(
+            Err(ParseTokenError { message: "Unterminated string literal".into(), span: Span::new(2, 3), incomplete: false }),
+            parse_tokens("a \"bc"),
+        );
+        assert_eq!(
+            Err(ParseTokenError { message: "Invalid escape sequence: \\q".into(), span: Span::new(2, 4), incomplete: false }),
+            parse_tokens("\"a\\q\""),
+        );
+        assert_eq!(
+            Err(ParseTokenError { message: "Unterminated character literal".into(), span: Span::new(0, 1), incomplete: false }),
+            parse_tokens("'ab'"),
         );
     }
 
+    #[test]
+    fn test_parse_tokens_comments() {
+        assert_eq!(
+            Ok(vec![Let, Identifier("I".into()), DefineReduce, Identifier("x".into())]),
+            parse_tokens("let I {- identity {- nested -} -} = x -- (Lx.x\n"),
+        );
+        assert_eq!(Ok(vec![]), parse_tokens("-- only a comment"));
+        assert_eq!(Ok(vec![Identifier("a".into()), Identifier("b".into())]), parse_tokens("a -- c\nb"));
+        assert_eq!(
+            Err(ParseTokenError { message: "Unterminated block comment".into(), span: Span::new(2, 4), incomplete: true }),
+            parse_tokens("a {- {- -}"),
+        );
+        assert_eq!(
+            Err(ParseTokenError { message: "Invalid token: -".into(), span: Span::new(2, 3), incomplete: false }),
+            parse_tokens("a - b"),
+        );
+    }
+
+    #[test]
+    fn test_parse_tokens_spans() {
+        let spans: Vec<_> = Token::parse_all("(λx. xy)").unwrap().iter().map(|l| l.span).collect();
+        assert_eq!(
+            vec![Span::new(0, 1), Span::new(1, 3), Span::new(3, 4), Span::new(4, 5), Span::new(6, 8), Span::new(8, 9)],
+            spans,
+        );
+
+        let source = "(λx. xy)";
+        assert_eq!((6, 2), (spans[4].column(source), spans[4].width(source)));
+
+        let source = "a\n  bc";
+        let span = Span::new(4, 6);
+        assert_eq!((2, 3, "  bc"), (span.line(source), span.column(source), span.line_text(source)));
+    }
+
     #[test]
     fn test_parse_back_displayed() {
         let tokens = vec![
-            ParenOpen, ParenClose, Lambda, Dot, Let, DefineReduce, DefineSuspend, Identifier("x".into())
+            ParenOpen, ParenClose, BracketOpen, BracketClose, AngleOpen, AngleClose, Comma, Lambda, Dot, Let, In, Where, DefineReduce, DefineSuspend, Identifier("x".into()),
+            Numeral(42), Power(3), Index(7), Text("a \"b\"\n".into()), Character('\''), Character('λ')
         ];
 
         let text = tokens.iter()
@@ -131,6 +472,34 @@ mod test {
                     .collect::<Vec<_>>()
                     .join(" ");
 
-        assert_eq!(Ok(tokens), Token::parse_all(&text));
+        assert_eq!(Ok(tokens), parse_tokens(&text));
+
+        // characters the lexer has no escape for are printed as they are
+        let characters: Vec<char> = (0..0x100).filter_map(::std::char::from_u32).chain(vec!['λ', '\u{2028}', '\u{feff}']).collect();
+        let mut tokens: Vec<_> = characters.iter().map(|&c| Character(c)).collect();
+        tokens.push(Text(characters.into_iter().collect()));
+
+        let text = tokens.iter().map(ToString::to_string).collect::<Vec<_>>().join(" ");
+        assert_eq!(Ok(tokens), parse_tokens(&text));
+    }
+
+    #[test]
+    fn test_parse_back_printed_terms() {
+        use ::random::TermGenerator;
+
+        let mut generator = TermGenerator::new(1);
+        for _ in 0..500 {
+            let text = generator.term(30).to_string();
+            let lexemes = Token::parse_all(&text).unwrap();
+
+            // every lexeme spans exactly the text of its token
+            for lexeme in &lexemes {
+                let source = &text[lexeme.span.start..lexeme.span.end];
+                assert_eq!(vec![&lexeme.token], parse_tokens(source).unwrap().iter().collect::<Vec<_>>(), "{}", text);
+            }
+
+            let displayed = lexemes.iter().map(ToString::to_string).collect::<Vec<_>>().join(" ");
+            assert_eq!(parse_tokens(&text), parse_tokens(&displayed));
+        }
     }
 }
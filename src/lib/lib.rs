@@ -1,4 +1,13 @@
+pub mod decoder;
+pub mod encoding;
+pub mod graph;
 pub mod lambda;
 pub mod lexer;
+pub mod names;
+pub mod observer;
 pub mod parser;
+pub mod pretty;
+#[cfg(test)]
+mod random;
 pub mod runtime;
+pub mod stepper;
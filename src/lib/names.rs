@@ -0,0 +1,131 @@
+//! Folding terms back into the names of the bindings they are equal to.
+//!
+//! Terms use de Bruijn indices, so α-equivalent terms are equal and can be
+//! looked up by hashing.
+
+use ::lambda::{Name, Term};
+
+use std::collections::HashMap;
+
+/// An index from closed terms to the names of the bindings with that value.
+#[derive(Debug, Default, Clone)]
+pub struct NameIndex {
+    /// The names of each term, in the order they were bound.
+    names: HashMap<Term, Vec<String>>,
+    terms: HashMap<String, Term>,
+    combinators: HashMap<Term, &'static str>,
+}
+
+impl NameIndex {
+    /// Creates an index which names only the combinators `I`, `K`, `S`, `B`,
+    /// `C` and `W`.
+    pub fn new() -> NameIndex {
+        let x = |depth| Term::variable(Name::bound(depth));
+        let apply = Term::apply;
+        let lambdas = |count, body| (0..count).fold(body, |body, _| Term::lambda(body));
+
+        let combinators = vec![
+            (lambdas(1, x(1)), "I"),
+            (lambdas(2, x(2)), "K"),
+            (lambdas(3, apply(apply(x(3), x(1)), apply(x(2), x(1)))), "S"),
+            (lambdas(3, apply(x(3), apply(x(2), x(1)))), "B"),
+            (lambdas(3, apply(apply(x(3), x(1)), x(2))), "C"),
+            (lambdas(2, apply(apply(x(2), x(1)), x(1))), "W"),
+        ];
+
+        NameIndex { combinators: combinators.into_iter().collect(), ..NameIndex::default() }
+    }
+
+    /// Records that `name` is bound to `term`, replacing its earlier value.
+    /// Only closed terms are indexed, as a term with free variables or loose
+    /// indices means something else inside another term.
+    pub fn insert(&mut self, name: &str, term: &Term) {
+        if let Some(old) = self.terms.remove(name) {
+            let names = self.names.get_mut(&old).expect("indexed names have a term");
+            names.retain(|n| n != name);
+            if names.is_empty() {
+                self.names.remove(&old);
+            }
+        }
+
+        if term.is_closed() {
+            self.names.entry(term.clone()).or_default().push(name.to_owned());
+            self.terms.insert(name.to_owned(), term.clone());
+        }
+    }
+
+    /// The names of `term` in the order they were bound, or the name of the
+    /// combinator it is if no binding has its value.
+    pub fn names(&self, term: &Term) -> Vec<&str> {
+        match self.names.get(term) {
+            Some(names) => names.iter().map(|name| &**name).collect(),
+            None => self.combinators.get(term).into_iter().cloned().collect(),
+        }
+    }
+
+    /// Replaces every outermost subterm of `term` which has a name by its
+    /// first name, as a free variable.
+    pub fn fold(&self, term: &Term) -> Term {
+        if let Some(&name) = self.names(term).first() {
+            return Term::variable(Name::free(name.to_owned()));
+        }
+
+        match *term {
+            Term::Variable { .. } => term.clone(),
+            Term::Lambda { ref body, ref hint } => Term::named_lambda(hint.clone(), self.fold(body)),
+            Term::Application { ref applicand, ref argument } => {
+                Term::apply(self.fold(applicand), self.fold(argument))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ::lexer::Token;
+    use ::parser::parse;
+    use ::runtime::Statement;
+
+    fn parse_term(input: &str) -> Term {
+        match parse(&Token::parse_all(input).unwrap()) {
+            Ok(Statement::Expression(term)) => term,
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        let mut index = NameIndex::new();
+        index.insert("0", &parse_term("Lf x.x"));
+        index.insert("F", &parse_term("La b.b"));
+        index.insert("K", &parse_term("Lx y.x"));
+        index.insert("free", &parse_term("Lx.y"));
+        index.insert("loose", &parse_term("Lx.↑2"));
+
+        assert_eq!(vec!["0", "F"], index.names(&parse_term("Lx y.y")));
+        assert_eq!(vec!["K"], index.names(&parse_term("Lx y.x")));
+        assert_eq!(vec!["W"], index.names(&parse_term("Lf x.f x x")));
+        assert!(index.names(&parse_term("Lx.y")).is_empty());
+        assert!(index.names(&parse_term("Lx.↑2")).is_empty());
+
+        index.insert("0", &parse_term("Lx.x"));
+        assert_eq!(vec!["F"], index.names(&parse_term("Lx y.y")));
+        assert_eq!(vec!["0"], index.names(&parse_term("Lx.x")));
+    }
+
+    #[test]
+    fn test_fold() {
+        let mut index = NameIndex::new();
+        index.insert("0", &parse_term("Lf x.x"));
+        index.insert("succ", &parse_term("Ln f x.f (n f x)"));
+
+        let term = parse_term("(Ln f x.f (n f x)) ((Ln f x.f (n f x)) (Lf x.x))");
+        assert_eq!(parse_term("succ (succ 0)"), index.fold(&term));
+        assert_eq!(parse_term("Ly.y I (K y)"), index.fold(&parse_term("Ly.y (Lx.x) ((Lx y.x) y)")));
+
+        // the body of `λx.x x` has the loose index bound to `loose`
+        index.insert("loose", &parse_term("↑1"));
+        assert_eq!(parse_term("Lx.x x"), index.fold(&parse_term("Lx.x x")));
+    }
+}
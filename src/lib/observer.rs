@@ -0,0 +1,36 @@
+//! Hooks into the reduction loop of `runtime::Environment`.
+
+use ::lambda::{Rule, Term};
+
+#[derive(Debug, PartialEq)]
+pub enum Event<'a> {
+    /// A reduction step by `rule` produced `term`; `index` counts steps
+    /// from 1.
+    Step { index: usize, rule: Rule, term: &'a Term },
+    /// Evaluation finished with `term`.
+    NormalForm { reductions: usize, term: &'a Term },
+    /// Evaluation is aborted because the reduction limit was exceeded.
+    LimitReached { reductions: usize, term: &'a Term },
+    /// Step `index` reproduced an earlier term, so evaluation can not
+    /// terminate.
+    CycleDetected { index: usize, term: &'a Term },
+}
+
+/// Tells the environment whether to go on after an event. Returning `Stop`
+/// from a `Step` event aborts the evaluation; for the other events the
+/// evaluation is over anyway.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+pub trait Observer: Send {
+    fn notify(&mut self, event: &Event) -> Control;
+}
+
+impl<F: FnMut(&Event) -> Control + Send> Observer for F {
+    fn notify(&mut self, event: &Event) -> Control {
+        self(event)
+    }
+}
@@ -1,19 +1,85 @@
-use ::lexer::Token;
-use ::lambda::{Term, Name};
+use ::lexer::{Lexeme, Span, Token};
+use ::encoding::{self, NumeralEncoding};
+use ::lambda::{Hint, Term, Name, MAX_POWER};
 use ::runtime::{Binding, BindMode, Statement};
 
 use std::collections::HashMap;
+use std::error;
 use std::fmt;
 use std::string::ToString;
 
 #[derive(Debug, PartialEq)]
 pub enum ParseError<'a> {
-    ExpectedToken(Vec<&'static str>, &'a Token),
-    EmptyExpression,
-    NotStartOfExpression(&'a Token),
+    ExpectedToken(Vec<&'static str>, &'a Lexeme),
+    /// Parentheses without an expression, ended by the given token.
+    EmptyExpression(&'a Lexeme),
+    NotStartOfExpression(&'a Lexeme),
+    /// The literal is too large for its encoding.
+    LiteralTooLarge(&'a Lexeme),
     EOF(Vec<&'static str>),
     UnboundVariable(String),
-    TrailingTokens(&'a[Token]),
+    TrailingTokens(&'a[Lexeme]),
+}
+
+impl<'a> ParseError<'a> {
+    /// The source span of the offending tokens, if the error points at any.
+    pub fn span(&self) -> Option<Span> {
+        use self::ParseError::*;
+
+        match *self {
+            ExpectedToken(_, lexeme) | EmptyExpression(lexeme) | NotStartOfExpression(lexeme) |
+            LiteralTooLarge(lexeme) => Some(lexeme.span),
+            TrailingTokens(lexemes) => {
+                let first = lexemes.first()?;
+                let last = lexemes.last()?;
+                Some(Span::new(first.span.start, last.span.end))
+            }
+            EOF(_) | UnboundVariable(_) => None,
+        }
+    }
+}
+
+/// Turns a token pattern as written in the parser into the text the user
+/// would type, e.g. `ParenClose` into `)`.
+fn describe(pattern: &'static str) -> &'static str {
+    match pattern.split('(').next().unwrap().trim() {
+        "ParenOpen" => "`(`",
+        "ParenClose" => "`)`",
+        "BracketOpen" => "`[`",
+        "BracketClose" => "`]`",
+        "AngleOpen" => "`<`",
+        "AngleClose" => "`>`",
+        "Comma" => "`,`",
+        "Lambda" => "`λ`",
+        "Dot" => "`.`",
+        "Identifier" => "identifier",
+        "Index" => "de Bruijn index",
+        "Numeral" => "numeral",
+        "Text" => "string",
+        "Character" => "character",
+        "Let" => "`let`",
+        "In" => "`in`",
+        "Where" => "`where`",
+        "DefineReduce" => "`=`",
+        "DefineSuspend" => "`:=`",
+        _ => pattern,
+    }
+}
+
+fn one_of(expected: &[&'static str]) -> String {
+    match expected.split_last() {
+        Some((last, [])) => last.to_string(),
+        Some((last, init)) => format!("{} or {}", init.join(", "), last),
+        None => "nothing".into(),
+    }
+}
+
+/// Points out a keyword where a name may have been meant.
+fn reserved(lexeme: &Lexeme) -> String {
+    match lexeme.token {
+        Token::Let | Token::In | Token::Where => format!(" (`{}` is a reserved word)", lexeme),
+        _ => String::new(),
+    }
 }
 
 impl<'a> fmt::Display for ParseError<'a> {
@@ -21,28 +87,46 @@ impl<'a> fmt::Display for ParseError<'a> {
         use self::ParseError::*;
 
         match *self {
-            ExpectedToken(ref patterns, ref got_token) => {
-                write!(f, "Expected any of: {} but got token '{}'", patterns.join(", "), got_token)
+            ExpectedToken(ref expected, ref got_token) => {
+                write!(f, "Expected {} but got `{}`{}", one_of(expected), got_token, reserved(got_token))
             }
-            EmptyExpression => write!(f, "Empty subexpression"),
+            EmptyExpression(_) => write!(f, "Empty subexpression"),
             NotStartOfExpression(ref got_token) => {
-                write!(f, "Invalid token at start of expression: '{}'", got_token)
+                write!(f, "Invalid token at start of expression: `{}`{}", got_token, reserved(got_token))
             }
-            EOF(ref patterns) => {
-                write!(f, "Got EOF while expecting any of: {}", patterns.join(", "))
+            LiteralTooLarge(ref literal) => write!(f, "Literal too large: `{}`", literal),
+            EOF(ref expected) => {
+                write!(f, "Unexpected end of input, expected {}", one_of(expected))
             }
-            UnboundVariable(ref variable) => write!(f, "Unbound variable: '{}'", variable),
+            UnboundVariable(ref variable) => write!(f, "Unbound variable: `{}`", variable),
             TrailingTokens(ref tokens) => {
-                write!(f, "Trailing tokens: '{}'",
-                       tokens.iter().map(ToString::to_string).collect::<Vec<_>>().join(" "))
+                write!(f, "Trailing tokens: `{}`{}",
+                       tokens.iter().map(ToString::to_string).collect::<Vec<_>>().join(" "),
+                       tokens.first().map_or(String::new(), reserved))
             }
         }
     }
 }
 
-pub fn parse(tokens: &[Token]) -> Result<Statement, ParseError> {
+impl<'a> error::Error for ParseError<'a> {}
+
+/// Switches for syntax that can not be told apart from the plain grammar.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Every binder is a single letter, so `λxyz.` binds `x`, `y` and `z`
+    /// instead of a variable named `xyz`.
+    pub single_letter_binders: bool,
+    /// The encoding `#n` literals expand to.
+    pub numerals: NumeralEncoding,
+}
+
+pub fn parse<'a>(tokens: &'a [Lexeme]) -> Result<Statement, ParseError<'a>> {
+    parse_with(tokens, ParseOptions::default())
+}
+
+pub fn parse_with<'a>(tokens: &'a [Lexeme], options: ParseOptions) -> Result<Statement, ParseError<'a>> {
     let mut symbols = SymbolTable::new();
-    let state = ParseState { lambda_depth: 0, symbols: &mut symbols };
+    let state = ParseState { lambda_depth: 0, symbols: &mut symbols, options };
 
     parse_toplevel(tokens, state)
         .map_err(|e| e.0)
@@ -55,25 +139,62 @@ pub fn parse(tokens: &[Token]) -> Result<Statement, ParseError> {
         })
 }
 
-type ParseResult<'a, 'b, T> = Result<(T, &'a[Token], ParseState<'b>), (ParseError<'a>, ParseState<'b>)>;
+/// Parses two expressions written one after the other, as in
+/// `(mult #2 #3) (plus #3 #3)`. The first one is a single operand, such as a
+/// variable, a literal or a parenthesized expression, and the second one is
+/// all the rest.
+pub fn parse_pair_with<'a>(tokens: &'a [Lexeme], options: ParseOptions) -> Result<(Term, Term), ParseError<'a>> {
+    let mut symbols = SymbolTable::new();
+    let state = ParseState { lambda_depth: 0, symbols: &mut symbols, options };
+
+    let (left, tokens, state) = parse_operand(tokens, state).map_err(|e| e.0)?;
+    let (right, remaining, _) = parse_expression(tokens, state).map_err(|e| e.0)?;
+    if remaining.is_empty() {
+        Ok((left, right))
+    } else {
+        Err(ParseError::TrailingTokens(remaining))
+    }
+}
+
+type ParseResult<'a, 'b, T> = Result<(T, &'a[Lexeme], ParseState<'b>), (ParseError<'a>, ParseState<'b>)>;
 type LambdaDepth = u32;
 type SymbolTable = HashMap<String, LambdaDepth>;
 struct ParseState<'a> {
     lambda_depth: LambdaDepth,
     symbols: &'a mut SymbolTable,
+    options: ParseOptions,
+}
+
+impl<'a> ParseState<'a> {
+    /// Binds `name` to a new innermost binder, returning the binding it
+    /// shadows.
+    fn bind(&mut self, name: &str) -> Option<LambdaDepth> {
+        let shadowed = self.symbols.insert(name.to_owned(), self.lambda_depth);
+        self.lambda_depth += 1;
+        shadowed
+    }
+
+    /// Undoes `bind`, recovering the shadowed binding if there was one.
+    fn unbind(&mut self, name: String, shadowed: Option<LambdaDepth>) {
+        self.lambda_depth -= 1;
+        match shadowed {
+            Some(lambda_depth) => self.symbols.insert(name, lambda_depth),
+            None => self.symbols.remove(&name),
+        };
+    }
 }
 
 macro_rules! expect_token {
     (($tokens:expr, $state:expr) { $($token:pat => $found:expr),* }) => {{
         match $tokens.split_first() {
             $(
-            Some(($token, rest)) => {
+            Some((Lexeme { token: $token, .. }, rest)) => {
                 ($found, rest)
             }
             ),*
-            None => return Err((ParseError::EOF(vec![$( stringify!($token) ),*]), $state)),
+            None => return Err((ParseError::EOF(vec![$( describe(stringify!($token)) ),*]), $state)),
             _ => return Err((ParseError::ExpectedToken(
-                vec![$( stringify!($token) ),*],
+                vec![$( describe(stringify!($token)) ),*],
                 $tokens.first().unwrap()),
                 $state,
             )),
@@ -96,28 +217,37 @@ macro_rules! try_expect_token {
         #[allow(unused_variables)]
         match $tokens.split_first() {
             $(
-            Some(($token, $rest)) => { $found }
+            Some((Lexeme { token: $token, .. }, $rest)) => { $found }
             ),*
-            None => return Err((ParseError::EOF(vec![$( stringify!($token) ),*]), $state)),
+            None => return Err((ParseError::EOF(vec![$( describe(stringify!($token)) ),*]), $state)),
             _ => $failed
         }
     }};
 }
 
-fn parse_toplevel<'a, 'b>(tokens: &'a[Token], state: ParseState<'b>) -> ParseResult<'a, 'b, Statement> {
+fn parse_toplevel<'a, 'b>(tokens: &'a[Lexeme], state: ParseState<'b>) -> ParseResult<'a, 'b, Statement> {
     use self::Token::*;
     use self::Statement::*;
 
     try_expect_token! {
         (tokens, _, state) {
-            Let => parse_let_statement(tokens, state).map(|(b, t, s)| (LetStatement(b), t, s))
+            Let => {
+                let (binding, rest, state) = parse_let_statement(tokens, state)?;
+                match rest.first() {
+                    // `let x = e in body` is an expression after all
+                    Some(Lexeme { token: In, .. }) => {
+                        parse_expression(tokens, state).map(|(e, t, s)| (Expression(e), t, s))
+                    }
+                    _ => Ok((LetStatement(binding), rest, state)),
+                }
+            }
         } else {
             parse_expression(tokens, state).map(|(e, t, s)| (Expression(e), t, s))
         }
     }
 }
 
-fn parse_let_statement<'a, 'b>(tokens: &'a[Token], state: ParseState<'b>) -> ParseResult<'a, 'b, Binding> {
+fn parse_let_statement<'a, 'b>(tokens: &'a[Lexeme], state: ParseState<'b>) -> ParseResult<'a, 'b, Binding> {
     use self::Token::*;
 
     let (_, tokens) = expect_token!(Let, tokens, state);
@@ -133,9 +263,148 @@ fn parse_let_statement<'a, 'b>(tokens: &'a[Token], state: ParseState<'b>) -> Par
     Ok((Binding::new(name, term, mode), tokens, state))
 }
 
-fn parse_expression<'a, 'b>(tokens: &'a[Token], state: ParseState<'b>) -> ParseResult<'a, 'b, Term> {
+/// Parses an expression followed by `where` clauses. `body where x = e`
+/// stands for `(λx.body) e`, and a clause is in scope of the body and of the
+/// values of all clauses before it.
+fn parse_expression<'a, 'b>(tokens: &'a[Lexeme], mut state: ParseState<'b>) -> ParseResult<'a, 'b, Term> {
+    use self::Token::*;
+
+    // the names must be bound before the body is parsed, the last clause
+    // being the outermost binder
+    let names = where_names(tokens);
+    let mut shadowed = vec![];
+    for name in names.iter().rev() {
+        shadowed.push(state.bind(name));
+    }
+
+    let (mut expr, mut tokens, mut state) = parse_application(tokens, state)?;
+
+    for (name, shadowed) in names.into_iter().zip(shadowed.into_iter().rev()) {
+        let (_, rest) = expect_token!(Where, tokens, state);
+        let (_, rest) = expect_token!(Identifier(_), rest, state);
+        let (_, rest) = expect_token!(DefineReduce, rest, state);
+
+        let hint = Hint::new(&name);
+        state.unbind(name, shadowed);
+        let (value, rest, new_state) = parse_application(rest, state)?;
+
+        expr = Term::apply(Term::named_lambda(hint, expr), value);
+        tokens = rest;
+        state = new_state;
+    }
+
+    Ok((expr, tokens, state))
+}
+
+/// Collects the names bound by the `where` clauses of the expression
+/// starting at `tokens`, skipping nested brackets and local `let`s.
+fn where_names(tokens: &[Lexeme]) -> Vec<String> {
+    use self::Token::*;
+
+    let mut names = vec![];
+    let mut parens = 0;
+    let mut lets = 0;
+    let mut iterator = tokens.iter();
+
+    while let Some(lexeme) = iterator.next() {
+        match lexeme.token {
+            ParenOpen | BracketOpen | AngleOpen => parens += 1,
+            ParenClose | BracketClose | AngleClose | Comma if parens == 0 => break,
+            ParenClose | BracketClose | AngleClose => parens -= 1,
+            Let if parens == 0 => lets += 1,
+            In if parens == 0 && lets == 0 => break,
+            In if parens == 0 => lets -= 1,
+            Where if parens == 0 && lets == 0 => {
+                if let Some(Lexeme { token: Identifier(name), .. }) = iterator.next() {
+                    names.push(name.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    names
+}
+
+/// Parses an application of one or more atoms, left-associatively. A lambda
+/// or a local `let` may appear as the last argument and extends as far right
+/// as possible.
+fn parse_application<'a, 'b>(mut tokens: &'a[Lexeme], mut state: ParseState<'b>) -> ParseResult<'a, 'b, Term> {
+    use self::Token::*;
+
+    let mut expr = None;
+
+    while tokens.first().map_or(false, |lexeme| starts_operand(&lexeme.token)) {
+        let (term, new_tokens, new_state) = parse_operand(tokens, state)?;
+
+        // `f^n x` applies `f` n times, taking the following operand as `x`
+        let (term, new_tokens, new_state) = match new_tokens.split_first() {
+            Some((lexeme @ Lexeme { token: Power(_), .. }, rest)) => parse_power(term, lexeme, rest, new_state)?,
+            _ => (term, new_tokens, new_state),
+        };
+
+        expr = match expr {
+            Some(t) => Some(Term::apply(t, term)),
+            _ => Some(term),
+        };
+        state = new_state;
+        tokens = new_tokens;
+    }
+
+    match (expr, tokens.first()) {
+        (Some(term), _) => Ok((term, tokens, state)),
+        (None, Some(lexeme @ Lexeme { token: ParenClose, .. })) => {
+            Err((ParseError::EmptyExpression(lexeme), state))
+        }
+        (None, Some(lexeme)) => Err((ParseError::NotStartOfExpression(lexeme), state)),
+        (None, None) => Err((ParseError::EOF(vec!["expression"]), state)),
+    }
+}
+
+/// Whether `token` starts an atom, a lambda or a local `let`.
+fn starts_operand(token: &Token) -> bool {
+    use self::Token::*;
+
+    match *token {
+        Lambda | Let | Identifier(_) | Index(_) | Numeral(_) | Text(_) | Character(_) | BracketOpen | AngleOpen
+        | ParenOpen => true,
+        _ => false,
+    }
+}
+
+fn parse_operand<'a, 'b>(tokens: &'a[Lexeme], state: ParseState<'b>) -> ParseResult<'a, 'b, Term> {
     use self::Token::*;
-    
+
+    match tokens.first() {
+        Some(Lexeme { token: Lambda, .. }) => parse_lambda(tokens, state),
+        Some(Lexeme { token: Let, .. }) => parse_local_let(tokens, state),
+        _ => parse_atom(tokens, state),
+    }
+}
+
+/// Parses the operand `x` of `f^n x` after the `^n` token `power`, and
+/// returns `f (f (... x))` with `n` applications of `function`.
+fn parse_power<'a, 'b>(function: Term, power: &'a Lexeme, tokens: &'a[Lexeme], state: ParseState<'b>) -> ParseResult<'a, 'b, Term> {
+    let n = match power.token {
+        Token::Power(n) if n <= MAX_POWER => n,
+        _ => return Err((ParseError::LiteralTooLarge(power), state)),
+    };
+
+    match tokens.first() {
+        Some(lexeme) if starts_operand(&lexeme.token) => {
+            let (operand, tokens, state) = parse_operand(tokens, state)?;
+            let term = (0..n).fold(operand, |term, _| Term::apply(function.clone(), term));
+            Ok((term, tokens, state))
+        }
+        Some(lexeme) => Err((ParseError::NotStartOfExpression(lexeme), state)),
+        None => Err((ParseError::EOF(vec!["expression"]), state)),
+    }
+}
+
+/// Parses a variable, a literal or a bracketed expression.
+fn parse_atom<'a, 'b>(tokens: &'a[Lexeme], state: ParseState<'b>) -> ParseResult<'a, 'b, Term> {
+    use self::Token::*;
+
     try_expect_token! {
         (tokens, rest, state) {
             Identifier(name) => {
@@ -149,78 +418,155 @@ fn parse_expression<'a, 'b>(tokens: &'a[Token], state: ParseState<'b>) -> ParseR
                     }
                 }
             }
+            Index(n) => {
+                Ok((Term::variable(Name::bound(*n)), rest, state))
+            }
+            Numeral(n) => {
+                match state.options.numerals.encode(*n) {
+                    Some(term) => Ok((term, rest, state)),
+                    None => Err((ParseError::LiteralTooLarge(&tokens[0]), state)),
+                }
+            }
+            Text(text) => {
+                match encoding::encode_string(text) {
+                    Some(term) => Ok((term, rest, state)),
+                    None => Err((ParseError::LiteralTooLarge(&tokens[0]), state)),
+                }
+            }
+            Character(c) => {
+                match NumeralEncoding::Church.encode(*c as u32) {
+                    Some(term) => Ok((term, rest, state)),
+                    None => Err((ParseError::LiteralTooLarge(&tokens[0]), state)),
+                }
+            }
             ParenOpen => {
-                let tokens = rest;
-
-                let (expr, tokens, state) = try_expect_token! {
-                    (tokens, _, state) {
-                        Lambda => parse_lambda(tokens, state)?
-                    } else {
-                        parse_application(tokens, state)?
-                    }
-                };
-
+                let (expr, tokens, state) = parse_expression(rest, state)?;
                 let (_, tokens) = expect_token!(ParenClose, tokens, state);
 
                 Ok((expr, tokens, state))
             }
+            BracketOpen => {
+                let (elements, tokens, state) = parse_elements(rest, state, |token| *token == BracketClose)?;
+                let (_, tokens) = expect_token!(BracketClose, tokens, state);
+
+                Ok((encoding::encode_list(elements), tokens, state))
+            }
+            AngleOpen => {
+                let (elements, tokens, state) = parse_elements(rest, state, |token| *token == AngleClose)?;
+                let (_, tokens) = expect_token!(AngleClose, tokens, state);
+
+                Ok((encoding::encode_tuple(elements), tokens, state))
+            }
         } else {
             Err((ParseError::NotStartOfExpression(tokens.first().unwrap()), state))
         }
     }
 }
 
-fn parse_application<'a, 'b>(mut tokens: &'a[Token], mut state: ParseState<'b>) -> ParseResult<'a, 'b, Term> {
-    let mut expr = None;
+/// Parses a lambda with one or more binders, `λx y z.body` being short for
+/// `λx.λy.λz.body`. A lambda without binders, `λ.body`, has a binder that
+/// can only be referred to by a de Bruijn index.
+fn parse_lambda<'a, 'b>(tokens: &'a[Lexeme], state: ParseState<'b>) -> ParseResult<'a, 'b, Term> {
+    use self::Token::*;
+    let (_, mut tokens) = expect_token!(Lambda, tokens, state);
 
-    loop {
-        match parse_expression(tokens, state) {
-            Ok((term, new_tokens, new_state)) => { 
-                expr = match expr {
-                    Some(t) => Some(Term::apply(t, term)),
-                    _ => Some(term),
-                };
-                state = new_state;
-                tokens = new_tokens;
-            }
-            Err((ParseError::NotStartOfExpression(_), err_state)) => {
-                state = err_state;
-                break;
+    let mut names = vec![];
+    while let Some((Lexeme { token: Identifier(name), .. }, rest)) = tokens.split_first() {
+        names.push(name.clone());
+        tokens = rest;
+    }
+    let (_, tokens) = if names.is_empty() {
+        expect_token! {
+            (tokens, state) {
+                Identifier(_) => (),
+                Dot => ()
             }
-            e => return e,
         }
-    } 
+    } else {
+        expect_token!(Dot, tokens, state)
+    };
+
+    if names.is_empty() {
+        let mut state = state;
+        state.lambda_depth += 1;
+        let (body, tokens, mut state) = parse_application(tokens, state)?;
+        state.lambda_depth -= 1;
+        return Ok((Term::lambda(body), tokens, state));
+    }
+
+    if state.options.single_letter_binders {
+        names = names.iter().flat_map(|name| name.chars().map(|c| c.to_string())).collect();
+    }
 
-    match expr {
-        Some(term) => Ok((term, tokens, state)),
-        _ => Err((ParseError::EmptyExpression, state)),
+    // perform shadowing bindings, later binders shadowing earlier ones
+    let mut state = state;
+    let shadowed: Vec<_> = names.iter().map(|name| state.bind(name)).collect();
+
+    let (mut body, tokens, mut state) = parse_application(tokens, state)?;
+
+    // recover old bindings in reverse order
+    for (name, shadowed) in names.into_iter().zip(shadowed).rev() {
+        body = Term::named_lambda(Hint::new(&name), body);
+        state.unbind(name, shadowed);
     }
+
+    Ok((body, tokens, state))
 }
 
-fn parse_lambda<'a, 'b>(tokens: &'a[Token], state: ParseState<'b>) -> ParseResult<'a, 'b, Term> {
+/// Parses the comma separated elements of a list or tuple literal, up to
+/// the token for which `is_end` holds.
+fn parse_elements<'a, 'b, F>(mut tokens: &'a[Lexeme], mut state: ParseState<'b>, is_end: F) -> ParseResult<'a, 'b, Vec<Term>>
+    where F: Fn(&Token) -> bool
+{
     use self::Token::*;
-    let (_, tokens) = expect_token!(Lambda, tokens, state);
-    let (name, tokens) = expect_token!(Identifier(name) => name.clone(), tokens, state);
-    let (_, tokens) = expect_token!(Dot, tokens, state);
-
-    // perform shadowing binding
-    let old_binding = state.symbols.insert(name.clone(), state.lambda_depth);
-    let state = ParseState { lambda_depth: state.lambda_depth + 1, symbols: state.symbols };
 
-    let (body, tokens, state) = parse_expression(tokens, state)?;
+    let mut elements = vec![];
+    if tokens.first().map_or(false, |lexeme| is_end(&lexeme.token)) {
+        return Ok((elements, tokens, state));
+    }
 
-    let state = ParseState { lambda_depth: state.lambda_depth - 1, symbols: state.symbols };
-    // recover old binding if present
-    if let Some(lambda_depth) = old_binding {
-        state.symbols.insert(name, lambda_depth);
+    loop {
+        let (element, rest, new_state) = parse_expression(tokens, state)?;
+        elements.push(element);
+        tokens = rest;
+        state = new_state;
+
+        match tokens.split_first() {
+            Some((Lexeme { token: Comma, .. }, rest)) => tokens = rest,
+            _ => return Ok((elements, tokens, state)),
+        }
     }
+}
 
-    Ok((Term::lambda(body), tokens, state))
+/// Parses `let x = e in body`, which stands for `(λx.body) e`. Like a lambda
+/// body, `body` extends as far right as possible.
+fn parse_local_let<'a, 'b>(tokens: &'a[Lexeme], state: ParseState<'b>) -> ParseResult<'a, 'b, Term> {
+    use self::Token::*;
+    let (_, tokens) = expect_token!(Let, tokens, state);
+    let (name, tokens) = expect_token!(Identifier(name) => name.clone(), tokens, state);
+    let (_, tokens) = expect_token!(DefineReduce, tokens, state);
+    let (value, tokens, state) = parse_application(tokens, state)?;
+    let (_, tokens) = expect_token!(In, tokens, state);
+
+    let mut state = state;
+    let shadowed = state.bind(&name);
+    let (body, tokens, mut state) = parse_application(tokens, state)?;
+    let hint = Hint::new(&name);
+    state.unbind(name, shadowed);
+
+    Ok((Term::apply(Term::named_lambda(hint, body), value), tokens, state))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::thread;
+
+    fn error_at(input: &str) -> (String, Option<Span>) {
+        let tokens = Token::parse_all(input).unwrap();
+        let error = parse(&tokens).unwrap_err();
+        (error.to_string(), error.span())
+    }
 
     #[test]
     fn test_parse_simple_lambda() {
@@ -292,4 +638,212 @@ mod test {
             parse(&tokens),
         );
     }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            ("Expected `.` but got `)`".into(), Some(Span::new(4, 5))),
+            error_at("(Lx )"),
+        );
+        assert_eq!(
+            ("Expected `=` or `:=` but got `(`".into(), Some(Span::new(6, 7))),
+            error_at("let I (Lx.x)"),
+        );
+        assert_eq!(
+            ("Unexpected end of input, expected `)`".into(), None),
+            error_at("(Lx.x"),
+        );
+        assert_eq!(
+            ("Empty subexpression".into(), Some(Span::new(4, 5))),
+            error_at("(a ())"),
+        );
+        assert_eq!(
+            ("Trailing tokens: `) b`".into(), Some(Span::new(2, 5))),
+            error_at("a ) b"),
+        );
+        assert_eq!(
+            ("Unexpected end of input, expected expression".into(), None),
+            error_at("let I = Lx."),
+        );
+    }
+
+    fn parse_term(input: &str) -> Term {
+        match parse(&Token::parse_all(input).unwrap()) {
+            Ok(Statement::Expression(term)) => term,
+            result => panic!("{:?}", result),
+        }
+    }
+
+    /// Runs `test` on a thread with room for recursing over terms nested
+    /// thousands of levels deep.
+    fn on_large_stack<F: FnOnce() + Send + 'static>(test: F) {
+        thread::Builder::new().stack_size(64 * 1024 * 1024).spawn(test).unwrap().join().unwrap();
+    }
+
+    #[test]
+    fn test_parse_without_parentheses() {
+        assert_eq!(parse_term("((f x) y)"), parse_term("f x y"));
+        assert_eq!(parse_term("(f (x y))"), parse_term("f (x y)"));
+        assert_eq!(parse_term("(Lx.(x y))"), parse_term("Lx.x y"));
+        assert_eq!(parse_term("(Lf.(Lx.(f (f x))))"), parse_term("Lf.Lx.f (f x)"));
+        assert_eq!(parse_term("((Lx.x) (Ly.y))"), parse_term("(Lx.x) Ly.y"));
+        assert_eq!(parse_term("(f (Lx.(x y)))"), parse_term("f Lx.x y"));
+        assert_eq!(parse_term("(Lx.((x (Ly.y)) x))"), parse_term("Lx.x (Ly.y) x"));
+    }
+
+    #[test]
+    fn test_parse_binder_lists() {
+        assert_eq!(parse_term("(Lx.(Ly.(Lz.(x y z))))"), parse_term("Lx y z.x y z"));
+        assert_eq!(parse_term("(Lx.(Lx.x))"), parse_term("Lx x.x"));
+        assert_eq!(parse_term("(Lxy.xy)"), parse_term("λxy.xy"));
+
+        let tokens = Token::parse_all("λxyz.x z (Lab.a)").unwrap();
+        assert_eq!(
+            Ok(Statement::Expression(parse_term("Lx y z.x z (La b.a)"))),
+            parse_with(&tokens, ParseOptions { single_letter_binders: true, ..ParseOptions::default() }),
+        );
+    }
+
+    #[test]
+    fn test_parse_binder_out_of_scope() {
+        assert_eq!(
+            Term::apply(Term::lambda(Term::variable(Name::bound(1))), Term::variable(Name::free("x".into()))),
+            parse_term("(Lx.x) x"),
+        );
+    }
+
+    #[test]
+    fn test_parse_local_bindings() {
+        assert_eq!(parse_term("(Lx.f x) a"), parse_term("let x = a in f x"));
+        assert_eq!(parse_term("g ((Lx.x x) (Ly.y))"), parse_term("g (let x = Ly.y in x x)"));
+        assert_eq!(parse_term("(Lx.(Ly.x y) b) a"), parse_term("let x = a in let y = b in x y"));
+        assert_eq!(parse_term("(Lx.x) (x y)"), parse_term("let x = x y in x"));
+
+        assert_eq!(parse_term("(Lx.f x) a"), parse_term("f x where x = a"));
+        assert_eq!(parse_term("(Ly.(Lx.f x y) (g y)) a"), parse_term("f x y where x = g y where y = a"));
+        assert_eq!(parse_term("Lx.(Lx.x) a"), parse_term("Lx.(x where x = a)"));
+        assert_eq!(parse_term("(Lz.Lx.x z) a"), parse_term("Lx.x z where z = a"));
+        assert_eq!(parse_term("(Ly.(Lx.x y) b) a"), parse_term("(let x = b in x y) where y = a"));
+        assert_eq!(parse_term("(Ly.(Lx.x y) b) a"), parse_term("let x = b in x y where y = a"));
+
+        let tokens = Token::parse_all("let x := a in x").unwrap();
+        assert_eq!("Expected `=` but got `:=`", parse(&tokens).unwrap_err().to_string());
+
+        // the keywords can not be used as names
+        assert_eq!("Expected identifier but got `in` (`in` is a reserved word)", error_at("let in = x").0);
+        assert_eq!("Trailing tokens: `where` (`where` is a reserved word)", error_at("f where").0);
+        assert_eq!("Invalid token at start of expression: `in` (`in` is a reserved word)", error_at("Lx.in").0);
+    }
+
+    #[test]
+    fn test_parse_numerals() {
+        assert_eq!(parse_term("succ (Lf x.f (f x))"), parse_term("succ #2"));
+
+        let tokens = Token::parse_all("#1").unwrap();
+        let options = ParseOptions { numerals: NumeralEncoding::Scott, ..ParseOptions::default() };
+        assert_eq!(Ok(Statement::Expression(parse_term("Ls z.s (Ls z.z)"))), parse_with(&tokens, options));
+
+        let tokens = Token::parse_all("f #20").unwrap();
+        let options = ParseOptions { numerals: NumeralEncoding::Parigot, ..ParseOptions::default() };
+        assert_eq!(Some(Span::new(2, 5)), parse_with(&tokens, options).unwrap_err().span());
+    }
+
+    #[test]
+    fn test_parse_text() {
+        assert_eq!(parse_term("Lc n.c #104 (c #105 n)"), parse_term("\"hi\""));
+        assert_eq!(parse_term("Lc n.n"), parse_term("\"\""));
+        assert_eq!(parse_term("#97"), parse_term("'a'"));
+    }
+
+    #[test]
+    fn test_parse_lists_and_tuples() {
+        assert_eq!(parse_term("Lc n.c a (c (f b) n)"), parse_term("[a, f b]"));
+        assert_eq!(parse_term("Lc n.n"), parse_term("[]"));
+        assert_eq!(parse_term("Lx.Lc n.c x (c (Ly.y x) n)"), parse_term("Lx.[x, Ly.y x]"));
+        assert_eq!(parse_term("Ls.s a (Lc n.c b n) c"), parse_term("<a, [b], c>"));
+        assert_eq!(parse_term("Ls.s ((Lx.x) a)"), parse_term("<x where x = a>"));
+
+        let tokens = Token::parse_all("[a, b>").unwrap();
+        assert_eq!("Expected `]` but got `>`", parse(&tokens).unwrap_err().to_string());
+    }
+
+    #[test]
+    fn test_parse_powers() {
+        assert_eq!(parse_term("Lf x.f (f (f x))"), parse_term("Lf x.f^3 x"));
+        assert_eq!(parse_term("(f (f x)) y"), parse_term("f^2 x y"));
+        assert_eq!(parse_term("g (f (f (h x)))"), parse_term("g f^2 (h x)"));
+        assert_eq!(parse_term("f (f Lx.x y)"), parse_term("f^2 Lx.x y"));
+        assert_eq!(parse_term("x"), parse_term("f^0 x"));
+
+        let tokens = Token::parse_all("f^2").unwrap();
+        assert_eq!(Err(ParseError::EOF(vec!["expression"])), parse(&tokens));
+        let tokens = Token::parse_all("f^2 )").unwrap();
+        assert_eq!(Err(ParseError::NotStartOfExpression(&tokens[2])), parse(&tokens));
+        let tokens = Token::parse_all("f^10001 x").unwrap();
+        assert_eq!(Err(ParseError::LiteralTooLarge(&tokens[1])), parse(&tokens));
+        assert_eq!(("Literal too large: `^10001`".into(), Some(Span::new(1, 7))), error_at("f^10001 x"));
+    }
+
+    #[test]
+    fn test_parse_pairs() {
+        let parse_pair = |input| {
+            let tokens = Token::parse_all(input).unwrap();
+            parse_pair_with(&tokens, ParseOptions::default()).map_err(|e| e.to_string())
+        };
+
+        assert_eq!(Ok((parse_term("a"), parse_term("b"))), parse_pair("a b"));
+        assert_eq!(Ok((parse_term("f #2 #3"), parse_term("g #3 #3"))), parse_pair("(f #2 #3) (g #3 #3)"));
+        assert_eq!(Ok((parse_term("[x]"), parse_term("f x y"))), parse_pair("[x] f x y"));
+        assert_eq!(Ok((parse_term("Lx.x"), parse_term("Ly.y"))), parse_pair("(Lx.x) Ly.y"));
+        assert_eq!(Err("Unexpected end of input, expected expression".into()), parse_pair("Lx.x y"));
+        assert_eq!(Err("Trailing tokens: `)`".into()), parse_pair("a b)"));
+    }
+
+    #[test]
+    fn test_parse_indices() {
+        assert_eq!(
+            Term::lambda(Term::apply(Term::variable(Name::bound(1)), Term::variable(Name::bound(3)))),
+            parse_term("Lx.x ↑3"),
+        );
+        assert_eq!(parse_term("Lx.x"), parse_term("Lx.↑1"));
+        assert_eq!(parse_term("Lx y.y x"), parse_term("λ.λ.↑1 ↑2"));
+        assert_eq!(parse_term("Lx y.y x"), parse_term("\\x.\\y.y x"));
+    }
+
+    #[test]
+    fn test_parse_back_printed_terms() {
+        use ::pretty::{Notation, PrettyPrinter};
+        use ::random::TermGenerator;
+
+        let mut generator = TermGenerator::new(2);
+        for i in 0..1000 {
+            let term = generator.term(1 + i % 40);
+            assert_eq!(term, parse_term(&term.to_string()), "{}", term);
+            assert_eq!(term, parse_term(&format!("{:#}", term)), "{:#}", term);
+
+            let notation = Notation::ALL[i % Notation::ALL.len()];
+            let printer = PrettyPrinter { width: i % 60, collapse_binders: i % 2 == 0, notation };
+            let pretty = printer.render(&term);
+            assert_eq!(term, parse_term(&pretty), "{}", pretty);
+        }
+    }
+
+    #[test]
+    fn test_parse_back_large_powers() {
+        use ::pretty::PrettyPrinter;
+
+        // as reached by `mult #200 #200`, beyond the largest power
+        on_large_stack(|| {
+            let f = || Term::variable(Name::bound(2));
+            let body = (0..40_001).fold(Term::variable(Name::bound(1)), |body, _| Term::apply(f(), body));
+            let term = Term::named_lambda(Hint::new("f"), Term::named_lambda(Hint::new("x"), body));
+
+            let pretty = PrettyPrinter::default().render(&term);
+            assert_eq!("λf.λx.f^10000 (f^10000 (f^10000 (f^10000 (f x))))", pretty);
+            assert_eq!(term, parse_term(&pretty));
+            let displayed = term.to_string();
+            assert_eq!("(λf.(λx.(f^10000 (f^10000 (f^10000 (f^10000 (f x)))))))", displayed);
+            assert_eq!(term, parse_term(&displayed));
+        });
+    }
 }
@@ -0,0 +1,504 @@
+//! Width-aware printing of terms with as few parentheses as possible.
+//!
+//! A term is first turned into a document of text, line breaks and groups as
+//! in Wadler's "A prettier printer". A group is laid out on one line if it
+//! fits into the remaining width; otherwise each of its line breaks starts a
+//! new, indented line.
+
+use ::encoding;
+use ::lambda::{Direction, Name, Rule, Scope, Term};
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug)]
+enum Doc {
+    Text(String),
+    /// Text around a marked subterm. Terminal escape codes in it take up no
+    /// columns.
+    Mark(String),
+    /// A line break, shown as `flat` when its group fits on one line.
+    Line { flat: &'static str },
+    /// Indents the lines started within by the given number of columns.
+    Nest(usize, Box<Doc>),
+    Group(Box<Doc>),
+    Concat(Vec<Doc>),
+}
+
+fn text<S: Into<String>>(s: S) -> Doc {
+    Doc::Text(s.into())
+}
+
+/// A break shown as a space on one line.
+fn line() -> Doc {
+    Doc::Line { flat: " " }
+}
+
+/// A break shown as nothing on one line.
+fn soft_line() -> Doc {
+    Doc::Line { flat: "" }
+}
+
+fn nest(indent: usize, doc: Doc) -> Doc {
+    Doc::Nest(indent, Box::new(doc))
+}
+
+fn group(doc: Doc) -> Doc {
+    Doc::Group(Box::new(doc))
+}
+
+fn parenthesize(doc: Doc) -> Doc {
+    Doc::Concat(vec![text("("), nest(1, doc), text(")")])
+}
+
+/// Where a term appears, which decides whether it needs parentheses.
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    /// The term is an argument, so an application must be parenthesized.
+    argument: bool,
+    /// Nothing follows the term, so a lambda may extend to the right.
+    last: bool,
+}
+
+const TOP: Position = Position { argument: false, last: true };
+
+/// A subterm to be marked, at `path` below the term being printed.
+#[derive(Debug, Clone, Copy)]
+struct Target<'a> {
+    path: &'a [Direction],
+    marks: (&'a str, &'a str),
+}
+
+impl<'a> Target<'a> {
+    /// The targets below the subterm reached by `steps`.
+    fn below(targets: &[Target<'a>], steps: &[Direction]) -> Vec<Target<'a>> {
+        targets.iter()
+            .filter(|target| target.path.starts_with(steps))
+            .map(|&Target { path, marks }| Target { path: &path[steps.len()..], marks })
+            .collect()
+    }
+}
+
+/// How lambdas and bound variables are written. The parser reads all of
+/// them.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Notation {
+    /// `λx.x`
+    Unicode,
+    /// `\x.x`, for terminals without Unicode.
+    Ascii,
+    /// `λ.↑1`, bound variables being written as de Bruijn indices.
+    DeBruijn,
+}
+
+impl Notation {
+    pub const ALL: [Notation; 3] = [
+        Notation::Unicode,
+        Notation::Ascii,
+        Notation::DeBruijn,
+    ];
+
+    pub fn name(self) -> &'static str {
+        use self::Notation::*;
+
+        match self {
+            Unicode => "unicode",
+            Ascii => "ascii",
+            DeBruijn => "debruijn",
+        }
+    }
+
+    fn lambda(self) -> &'static str {
+        match self {
+            Notation::Ascii => "\\",
+            _ => "λ",
+        }
+    }
+
+    /// The label of a reduction step by `rule`.
+    pub fn rule(self, rule: Rule) -> &'static str {
+        match (self, rule) {
+            (Notation::Ascii, Rule::Beta) => "beta",
+            (Notation::Ascii, Rule::Eta) => "eta",
+            (_, Rule::Beta) => "β",
+            (_, Rule::Eta) => "η",
+        }
+    }
+}
+
+impl Default for Notation {
+    fn default() -> Notation {
+        Notation::Unicode
+    }
+}
+
+impl fmt::Display for Notation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseNotationError(pub String);
+
+impl FromStr for Notation {
+    type Err = ParseNotationError;
+
+    fn from_str(s: &str) -> Result<Notation, ParseNotationError> {
+        Notation::ALL.iter()
+            .find(|notation| notation.name() == s)
+            .cloned()
+            .ok_or_else(|| ParseNotationError(format!("Unknown notation: {}", s)))
+    }
+}
+
+/// Prints terms in the notation of the parser: application by juxtaposition,
+/// lambdas extending as far right as possible, and lists and tuples in
+/// brackets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrettyPrinter {
+    /// The number of columns lines are broken to fit into where possible.
+    pub width: usize,
+    /// Whether nested lambdas share one binder list, as in `λf x.f x`.
+    /// Lambdas without names are never collapsed.
+    pub collapse_binders: bool,
+    pub notation: Notation,
+}
+
+impl Default for PrettyPrinter {
+    fn default() -> PrettyPrinter {
+        PrettyPrinter { width: 80, collapse_binders: false, notation: Notation::Unicode }
+    }
+}
+
+impl PrettyPrinter {
+    pub fn render(&self, term: &Term) -> String {
+        self.render_at(term, 0)
+    }
+
+    /// Renders `term` to be printed after `column` other characters on its
+    /// first line. Continuation lines are indented by `column`, too.
+    pub fn render_at(&self, term: &Term, column: usize) -> String {
+        let doc = self.doc(term, &mut Scope::new(term), TOP, &[]);
+        layout(&nest(column, doc), self.width, column)
+    }
+
+    /// Like `render_at`, but puts the opening and closing text of each mark
+    /// around the subterm at its path. Terminal escape codes in the marks do
+    /// not count towards the width. Lists and tuples containing a marked
+    /// subterm are printed as lambdas, so it can be told apart. Marks at
+    /// paths without a subterm are left out.
+    pub fn render_marked(&self, term: &Term, column: usize, marks: &[(&[Direction], &str, &str)]) -> String {
+        let targets: Vec<_> = marks.iter()
+            .filter(|&&(path, _, _)| term.subterm(path).is_some())
+            .map(|&(path, open, close)| Target { path, marks: (open, close) })
+            .collect();
+        let doc = self.doc(term, &mut Scope::new(term), TOP, &targets);
+        layout(&nest(column, doc), self.width, column)
+    }
+
+    fn doc(&self, term: &Term, symbols: &mut Scope, position: Position, targets: &[Target]) -> Doc {
+        if let Some(i) = targets.iter().position(|target| target.path.is_empty()) {
+            let (open, close) = targets[i].marks;
+            let mut inner = targets.to_vec();
+            inner.remove(i);
+            let doc = self.doc(term, symbols, position, &inner);
+            return Doc::Concat(vec![Doc::Mark(open.to_owned()), doc, Doc::Mark(close.to_owned())]);
+        }
+
+        match *term {
+            Term::Variable { ref name } if self.notation == Notation::DeBruijn => text(name.to_string()),
+            Term::Variable { ref name } => text(variable_name(name, symbols)),
+            Term::Application { .. } => {
+                let mut arguments = vec![];
+                let mut head = term;
+                while let Term::Application { ref applicand, ref argument } = *head {
+                    // a marked partial application is kept together as the
+                    // head
+                    let marked = !arguments.is_empty() && targets.iter().any(|target| {
+                        target.path.len() == arguments.len() && target.path.iter().all(|&d| d == Direction::Applicand)
+                    });
+                    if marked || head.power().is_some() {
+                        break;
+                    }
+                    arguments.push(&**argument);
+                    head = applicand;
+                }
+                arguments.reverse();
+
+                let parenthesized = position.argument;
+                let last = position.last || parenthesized;
+                let count = arguments.len();
+                let head_targets = Target::below(targets, &vec![Direction::Applicand; count]);
+                let mut docs = match head.power() {
+                    // `f^n x` is parsed like an application `f x`
+                    Some((function, n, operand)) => {
+                        let function = self.doc(function, symbols, TOP, &[]);
+                        let position = Position { argument: true, last: last && arguments.is_empty() };
+                        let operand_targets = Target::below(&head_targets, &vec![Direction::Argument; n as usize]);
+                        let operand = self.doc(operand, symbols, position, &operand_targets);
+                        vec![function, text(format!("^{}", n)), nest(2, Doc::Concat(vec![line(), operand]))]
+                    }
+                    None => vec![self.doc(head, symbols, Position { argument: false, last: false }, &head_targets)],
+                };
+                for (i, argument) in arguments.into_iter().enumerate() {
+                    let position = Position { argument: true, last: last && i + 1 == count };
+                    let mut steps = vec![Direction::Applicand; count - 1 - i];
+                    steps.push(Direction::Argument);
+                    let argument = self.doc(argument, symbols, position, &Target::below(targets, &steps));
+                    docs.push(nest(2, Doc::Concat(vec![line(), argument])));
+                }
+
+                let doc = group(Doc::Concat(docs));
+                if parenthesized { parenthesize(doc) } else { doc }
+            }
+            Term::Lambda { .. } => {
+                if targets.is_empty() {
+                    if let Some(literal) = encoding::decode_literal(term) {
+                        return self.literal(&literal, symbols);
+                    }
+                }
+
+                let mut names = vec![];
+                let mut body = term;
+                while let Term::Lambda { body: ref inner, ref hint } = *body {
+                    let collapse = self.collapse_binders && self.notation != Notation::DeBruijn;
+                    if !names.is_empty() && (!collapse || encoding::decode_literal(body).is_some()) {
+                        break;
+                    }
+                    let name = match self.notation {
+                        Notation::DeBruijn => String::new(),
+                        _ => symbols.binder_name(hint, inner),
+                    };
+                    symbols.push(name.clone());
+                    names.push(name);
+                    body = inner;
+                }
+
+                let body_targets = Target::below(targets, &vec![Direction::Body; names.len()]);
+                let body = self.doc(body, symbols, TOP, &body_targets);
+                for _ in 0..names.len() {
+                    symbols.pop();
+                }
+
+                let binders = text(format!("{}{}.", self.notation.lambda(), names.join(" ")));
+                let doc = group(Doc::Concat(vec![binders, nest(2, Doc::Concat(vec![soft_line(), body]))]));
+                if position.last { doc } else { parenthesize(doc) }
+            }
+        }
+    }
+
+    fn literal(&self, literal: &encoding::Literal, symbols: &mut Scope) -> Doc {
+        let (open, close) = literal.delimiters();
+        let mut elements = vec![soft_line()];
+        for (i, element) in literal.elements().iter().enumerate() {
+            if i > 0 {
+                elements.push(text(","));
+                elements.push(line());
+            }
+            elements.push(self.doc(element, symbols, TOP, &[]));
+        }
+
+        group(Doc::Concat(vec![text(open), nest(2, Doc::Concat(elements)), soft_line(), text(close)]))
+    }
+}
+
+fn variable_name(name: &Name, symbols: &[String]) -> String {
+    match *name {
+        Name::Bound { depth } if depth as usize <= symbols.len() => symbols[symbols.len() - depth as usize].clone(),
+        _ => name.to_string(),
+    }
+}
+
+/// The number of columns `s` takes up, leaving out terminal escape codes
+/// such as `\x1b[7m`.
+fn visible_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // a control sequence ends with a letter
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            width += 1;
+        }
+    }
+    width
+}
+
+/// Lays out `doc` starting at `column`, breaking the lines of every group
+/// that does not fit into `width`.
+fn layout(doc: &Doc, width: usize, mut column: usize) -> String {
+    let mut output = String::new();
+    // the documents still to be printed, last first, with their
+    // indentation and whether they are printed on one line
+    let mut pending = vec![(0, false, doc)];
+
+    while let Some((indent, flat, doc)) = pending.pop() {
+        match *doc {
+            Doc::Text(ref s) => {
+                output.push_str(s);
+                column += s.chars().count();
+            }
+            Doc::Mark(ref s) => {
+                output.push_str(s);
+                column += visible_width(s);
+            }
+            Doc::Line { flat: s } if flat => {
+                output.push_str(s);
+                column += s.chars().count();
+            }
+            Doc::Line { .. } => {
+                output.push('\n');
+                output.push_str(&" ".repeat(indent));
+                column = indent;
+            }
+            Doc::Nest(by, ref doc) => pending.push((indent + by, flat, doc)),
+            Doc::Group(ref doc) => {
+                let flat = flat || fits(doc, width as isize - column as isize, &pending);
+                pending.push((indent, flat, doc));
+            }
+            Doc::Concat(ref docs) => pending.extend(docs.iter().rev().map(|doc| (indent, flat, doc))),
+        }
+    }
+
+    output
+}
+
+/// Whether `doc` printed on one line, and whatever follows it up to the next
+/// line break, takes at most `remaining` columns.
+fn fits(doc: &Doc, mut remaining: isize, rest: &[(usize, bool, &Doc)]) -> bool {
+    let mut pending = vec![(true, doc)];
+    let mut rest = rest.iter().rev();
+
+    while remaining >= 0 {
+        let (flat, doc) = match pending.pop() {
+            Some(next) => next,
+            None => match rest.next() {
+                Some(&(_, flat, doc)) => (flat, doc),
+                None => return true,
+            }
+        };
+
+        match *doc {
+            Doc::Text(ref s) => remaining -= s.chars().count() as isize,
+            Doc::Mark(ref s) => remaining -= visible_width(s) as isize,
+            Doc::Line { flat: s } if flat => remaining -= s.chars().count() as isize,
+            Doc::Line { .. } => return true,
+            Doc::Nest(_, ref doc) | Doc::Group(ref doc) => pending.push((flat, doc)),
+            Doc::Concat(ref docs) => pending.extend(docs.iter().rev().map(|doc| (flat, doc))),
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ::lexer::Token;
+    use ::parser::parse;
+    use ::runtime::Statement;
+
+    fn parse_term(input: &str) -> Term {
+        match parse(&Token::parse_all(input).unwrap()) {
+            Ok(Statement::Expression(term)) => term,
+            result => panic!("{:?}", result),
+        }
+    }
+
+    fn render(input: &str) -> String {
+        PrettyPrinter::default().render(&parse_term(input))
+    }
+
+    #[test]
+    fn test_minimal_parentheses() {
+        assert_eq!("λf.λx.f (f x)", render("(Lf.(Lx.(f (f x))))"));
+        assert_eq!("f x y", render("((f x) y)"));
+        assert_eq!("f (g x) (λx.x) λy.y", render("f (g x) (Lx.x) (Ly.y)"));
+        assert_eq!("(λx.x x) λx.x x", render("(Lx.x x) (Lx.x x)"));
+        assert_eq!("f (λx.x) y", render("f (Lx.x) y"));
+        assert_eq!("[λx.x, f a, <a, b>]", render("[Lx.x, f a, <a, b>]"));
+        assert_eq!("λs.s a", render("<a>"));
+        assert_eq!("λf.λx.f^3 x", render("#3"));
+        assert_eq!("f^3 (g x) y", render("f (f (f (g x))) y"));
+        assert_eq!("h (f^3 x) (f (f x))", render("h (f (f (f x))) (f (f x))"));
+        assert_eq!("f^3 λx.x", render("f (f (f Lx.x))"));
+        assert_eq!("f^3 (λx.x) y", render("f (f (f Lx.x)) y"));
+
+        let printer = PrettyPrinter { collapse_binders: true, ..PrettyPrinter::default() };
+        assert_eq!("λf x.f x", printer.render(&parse_term("Lf.Lx.f x")));
+        assert_eq!("λx.[x, x]", printer.render(&parse_term("Lx.[x, x]")));
+    }
+
+    #[test]
+    fn test_notations() {
+        let term = parse_term("Lf x.f (Ly.y x) z [x]");
+        let printer = |notation| PrettyPrinter { notation, ..PrettyPrinter::default() };
+
+        assert_eq!("λf.λx.f (λy.y x) z [x]", printer(Notation::Unicode).render(&term));
+        assert_eq!("\\f.\\x.f (\\y.y x) z [x]", printer(Notation::Ascii).render(&term));
+        assert_eq!("λ.λ.↑2 (λ.↑1 ↑2) z [↑1]", printer(Notation::DeBruijn).render(&term));
+        assert_eq!("beta", Notation::Ascii.rule(Rule::Beta));
+        assert_eq!("η", Notation::DeBruijn.rule(Rule::Eta));
+
+        for &notation in Notation::ALL.iter() {
+            assert_eq!(Ok(notation), notation.name().parse());
+        }
+        assert!("latex".parse::<Notation>().is_err());
+    }
+
+    #[test]
+    fn test_line_breaking() {
+        let printer = PrettyPrinter { width: 24, ..PrettyPrinter::default() };
+        let term = parse_term("Lf.function argument (another argument) [first, second]");
+
+        assert_eq!(
+            "λf.\n  function\n    argument\n    (another argument)\n    [first, second]",
+            printer.render(&term)
+        );
+        assert_eq!("f x", printer.render_at(&parse_term("f x"), 20));
+        assert_eq!("f\n                      xs\n                      ys", printer.render_at(&parse_term("f xs ys"), 20));
+    }
+
+    #[test]
+    fn test_marks() {
+        use ::lambda::Direction::*;
+
+        let printer = PrettyPrinter::default();
+        let marked = |input: &str, path: &[Direction]| printer.render_marked(&parse_term(input), 0, &[(path, "<", ">")]);
+
+        assert_eq!("<(λx.x) y>", marked("(Lx.x) y", &[]));
+        assert_eq!("f <((λx.x) y)> z", marked("f ((Lx.x) y) z", &[Applicand, Argument]));
+        assert_eq!("λf.f <x>", marked("Lf.f x", &[Body, Argument]));
+        assert_eq!("f^3 <x>", marked("f (f (f x))", &[Argument, Argument, Argument]));
+        assert_eq!("λc.λn.c <((λx.x) y)> n", marked("[(Lx.x) y]", &[Body, Body, Applicand, Argument]));
+        assert_eq!("[(λx.x) y]", marked("[(Lx.x) y]", &[Argument]));
+
+        // several marks, one inside another
+        let term = parse_term("(Lx.x) ((Ly.y) a) ((Lz.z) b)");
+        let marks = [
+            (&[Applicand, Applicand][..], "1<", ">"),
+            (&[Applicand, Argument][..], "2<", ">"),
+            (&[Applicand][..], "3<", ">"),
+            (&[Argument][..], "4<", ">"),
+        ];
+        assert_eq!("3<1<(λx.x)> 2<((λy.y) a)>> 4<((λz.z) b)>", printer.render_marked(&term, 0, &marks));
+
+        // escape codes take up no columns, other marks do
+        let narrow = PrettyPrinter { width: 14, ..PrettyPrinter::default() };
+        let term = parse_term("f (g a) b");
+        let mark = |open, close| narrow.render_marked(&term, 0, &[(&[Applicand, Argument][..], open, close)]);
+        assert_eq!("f \x1b[7m(g a)\x1b[27m b", mark("\x1b[7m", "\x1b[27m"));
+        assert_eq!("f\n  [[[(g a)]]]\n  b", mark("[[[", "]]]"));
+    }
+
+    #[test]
+    fn test_parse_back() {
+        let printer = PrettyPrinter { width: 10, collapse_binders: true, notation: Notation::Unicode };
+        for input in &["Lf x.f (f x)", "Lf x.f^12 (f^4 x) f^3 x", "(Lx.x x) (Lx y.y x) z", "f (Lx.x) (g Ly.y)", "Lx.[x, <x, Ly.y>, x y]"] {
+            let term = parse_term(input);
+            assert_eq!(term, parse_term(&printer.render(&term)), "{}", input);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Pseudo-random terms for property tests.
+
+use ::encoding::{self, NumeralEncoding};
+use ::lambda::{Hint, Name, Term};
+
+/// The names given to free variables and binders. Binders share names with
+/// free variables and with each other, so printing has to rename them.
+const NAMES: [&str; 6] = ["x", "y", "f", "x1", "a", "0"];
+
+/// Generates terms from a seed, always the same ones for the same seed.
+/// Besides bound and free variables, the terms contain indices pointing
+/// outside of them, binders without names, and encoded numerals, lists and
+/// tuples.
+#[derive(Debug, Clone)]
+pub struct TermGenerator {
+    state: u64,
+}
+
+impl TermGenerator {
+    pub fn new(seed: u64) -> TermGenerator {
+        // xorshift gets stuck at zero
+        TermGenerator { state: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1 }
+    }
+
+    /// Returns a term of about `size` nodes.
+    pub fn term(&mut self, size: usize) -> Term {
+        self.term_at(size, 0)
+    }
+
+    fn term_at(&mut self, size: usize, depth: u32) -> Term {
+        if size <= 1 {
+            return self.variable(depth);
+        }
+
+        match self.below(12) {
+            0 => {
+                let n = self.below(6) as u32;
+                NumeralEncoding::Church.encode(n).expect("small numerals can be encoded")
+            }
+            1 => {
+                let elements = self.elements(size - 1, depth);
+                encoding::encode_list(elements)
+            }
+            2 => {
+                let elements = self.elements(size - 1, depth);
+                encoding::encode_tuple(elements)
+            }
+            3..=6 => {
+                let hint = match self.below(NAMES.len() + 1) {
+                    0 => Hint::default(),
+                    i => Hint::new(NAMES[i - 1]),
+                };
+                Term::named_lambda(hint, self.term_at(size - 1, depth + 1))
+            }
+            _ => {
+                let left = 1 + self.below(size - 1);
+                Term::apply(self.term_at(left, depth), self.term_at(size - left, depth))
+            }
+        }
+    }
+
+    fn variable(&mut self, depth: u32) -> Term {
+        match self.below(8) {
+            0 | 1 => Term::variable(Name::free(NAMES[self.below(NAMES.len())].into())),
+            2 => Term::variable(Name::bound(depth + 1 + self.below(3) as u32)),
+            _ if depth == 0 => Term::variable(Name::free(NAMES[self.below(NAMES.len())].into())),
+            _ => Term::variable(Name::bound(1 + self.below(depth as usize) as u32)),
+        }
+    }
+
+    fn elements(&mut self, size: usize, depth: u32) -> Vec<Term> {
+        let count = 1 + self.below(3);
+        (0..count).map(|_| self.term_at(size / count, depth)).collect()
+    }
+
+    /// Returns a number below `n`, which must not be zero.
+    fn below(&mut self, n: usize) -> usize {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_deterministic() {
+        let terms = |seed| {
+            let mut generator = TermGenerator::new(seed);
+            (0..20).map(|_| generator.term(15)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(terms(7), terms(7));
+        assert_ne!(terms(7), terms(8));
+    }
+}
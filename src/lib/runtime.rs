@@ -1,8 +1,17 @@
-use ::lambda::{self, Term, Strategy};
-use ::lexer::Token;
-use ::parser::parse;
+use ::decoder::Decoders;
+use ::graph::Graph;
+use ::lambda::{self, Equivalence, Eta, Rule, Term, Strategy};
+use ::lexer::{ParseTokenError, Span, Token};
+use ::names::NameIndex;
+use ::observer::{Control, Event, Observer};
+use ::parser::{self, parse_pair_with, parse_with, ParseOptions};
+use ::pretty::PrettyPrinter;
 use std::collections::{HashMap, HashSet};
+use std::error;
+use std::fmt;
 use std::iter;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum BindMode {
@@ -72,34 +81,226 @@ impl SymbolTable for () {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum EvaluationError {
-    TooManyReductions,
-    NonTerminating,
-    RecursiveBinding,
-    ParseError,
+    /// The input could not be tokenized or parsed. The span is missing when
+    /// the position of the error is not known.
+    ParseError { message: String, span: Option<Span> },
+    /// The term refers to the name it is being bound to.
+    RecursiveBinding { identifier: String },
+    /// More than `max_reductions` steps were needed.
+    TooManyReductions { identifier: String, partial: Term, reductions: usize },
+    /// A reduction step reproduced an earlier term.
+    NonTerminating { identifier: String, partial: Term, reductions: usize },
+    /// The evaluation was cancelled through its `CancellationToken`.
+    Interrupted { identifier: String, partial: Term, reductions: usize },
+    /// An observer asked for the evaluation to stop.
+    Stopped { identifier: String, partial: Term, reductions: usize },
 }
 
 use self::EvaluationError::*;
 
+impl EvaluationError {
+    /// The term reached before the evaluation was aborted.
+    pub fn partial_term(&self) -> Option<&Term> {
+        match *self {
+            TooManyReductions { ref partial, .. } |
+            NonTerminating { ref partial, .. } |
+            Interrupted { ref partial, .. } |
+            Stopped { ref partial, .. } => Some(partial),
+            _ => None,
+        }
+    }
+
+    /// The number of reductions performed before the evaluation was aborted.
+    pub fn reductions(&self) -> Option<usize> {
+        match *self {
+            TooManyReductions { reductions, .. } |
+            NonTerminating { reductions, .. } |
+            Interrupted { reductions, .. } |
+            Stopped { reductions, .. } => Some(reductions),
+            _ => None,
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        match *self {
+            ParseError { span, .. } => span,
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseTokenError> for EvaluationError {
+    fn from(error: ParseTokenError) -> EvaluationError {
+        ParseError { message: error.message, span: Some(error.span) }
+    }
+}
+
+impl<'a> From<parser::ParseError<'a>> for EvaluationError {
+    fn from(error: parser::ParseError<'a>) -> EvaluationError {
+        ParseError { message: error.to_string(), span: error.span() }
+    }
+}
+
+/// Leaves out the partial terms, for front ends to print in their notation.
+impl fmt::Display for EvaluationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let binding = |f: &mut fmt::Formatter, identifier: &str| {
+            if identifier == ANS {
+                Ok(())
+            } else {
+                write!(f, " in binding '{}'", identifier)
+            }
+        };
+
+        match *self {
+            ParseError { ref message, .. } => write!(f, "{}", message),
+            RecursiveBinding { ref identifier } => write!(f, "recursive binding of '{}'", identifier),
+            TooManyReductions { ref identifier, reductions, .. } => {
+                write!(f, "[too many reductions: {}", reductions)?;
+                binding(f, identifier)?;
+                write!(f, "]")
+            }
+            NonTerminating { ref identifier, reductions, .. } => {
+                write!(f, "[non-terminating: cycle after {} reductions", reductions)?;
+                binding(f, identifier)?;
+                write!(f, "]")
+            }
+            Interrupted { ref identifier, reductions, .. } => {
+                write!(f, "[interrupted after {} reductions", reductions)?;
+                binding(f, identifier)?;
+                write!(f, "]")
+            }
+            Stopped { ref identifier, reductions, .. } => {
+                write!(f, "[stopped by observer after {} reductions", reductions)?;
+                binding(f, identifier)?;
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+impl error::Error for EvaluationError {}
+
 pub type EvaluationResult<T> = Result<T, EvaluationError>;
 
+/// Something worth pointing out about an evaluation that did not prevent it
+/// from succeeding.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Diagnostic {
+    /// The binding replaced an existing binding of the same name.
+    Redefined(String),
+    /// The result still contains variables with no binding.
+    FreeVariables(Vec<String>),
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Diagnostic::Redefined(ref identifier) => write!(f, "redefined '{}'", identifier),
+            Diagnostic::FreeVariables(ref names) => write!(f, "free variables: {}", names.join(", ")),
+        }
+    }
+}
+
+/// The result of interpreting a single statement.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Evaluation {
+    /// The term as it was parsed, before bindings were substituted.
+    pub input: Term,
+    /// The reduced term, or `None` for bindings made with `:=`.
+    pub normal_form: Option<Term>,
+    /// The number of β-reduction steps.
+    pub reductions: usize,
+    /// The number of η-reduction or expansion steps.
+    pub eta_reductions: usize,
+    /// The name the result was bound to; `ANS` for bare expressions.
+    pub binding: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Evaluation {
+    fn new(input: Term, binding: String) -> Evaluation {
+        Evaluation {
+            input,
+            normal_form: None,
+            reductions: 0,
+            eta_reductions: 0,
+            binding,
+            diagnostics: vec![],
+        }
+    }
+}
+
+/// Name of the binding holding the result of the last bare expression.
+pub const ANS: &str = "ans";
+
+/// A flag shared between an `Environment` and other threads, which is checked
+/// before every reduction step so that a running evaluation can be aborted.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Clears the flag, so that the next evaluation runs.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
 pub struct Environment<T: SymbolTable = HashSymbolTable> {
     symbols: T,
+    names: NameIndex,
+    cancellation: CancellationToken,
+    observers: Vec<Box<dyn Observer>>,
     pub max_reductions: usize,
+    /// Whether front ends print the input and every reduction step; steps
+    /// are reported to observers, see `add_observer`.
     pub echo_enabled: bool,
+    pub strategy: Strategy,
+    /// Whether η steps follow β-reduction; `Eta::Reduce` evaluates to
+    /// βη-normal form.
+    pub eta: Eta,
+    pub parse_options: ParseOptions,
+    /// Used by front ends to show normal forms in readable form.
+    pub decoders: Decoders,
+    /// Used by front ends to lay out terms.
+    pub printer: PrettyPrinter,
+    /// Whether front ends show results with subterms replaced by the names
+    /// of bindings they are equal to.
+    pub fold_names: bool,
 }
 
 #[allow(unknown_lints,new_without_default)]
 impl<T: SymbolTable> Environment<T> {
     const MAX_REDUCTIONS_DEFAULT: usize = 5000;
-    const ANS: &'static str = "ans";
 
     pub fn new() -> Environment<T> where T: Default {
         Environment {
             symbols: T::default(),
+            names: NameIndex::new(),
+            cancellation: CancellationToken::new(),
+            observers: vec![],
             max_reductions: Self::MAX_REDUCTIONS_DEFAULT,
             echo_enabled: true,
+            strategy: Strategy::NormalOrder,
+            eta: Eta::Off,
+            parse_options: ParseOptions::default(),
+            decoders: Decoders::default(),
+            printer: PrettyPrinter::default(),
+            fold_names: false,
         }
     }
 
@@ -107,82 +308,229 @@ impl<T: SymbolTable> Environment<T> {
         &self.symbols
     }
 
-    fn add_binding(&mut self, mut binding: Binding) -> EvaluationResult<()> {
+    /// The names of all bindings except `ANS`, indexed by their values.
+    pub fn name_index(&self) -> &NameIndex {
+        &self.names
+    }
+
+    /// Returns a token which aborts the evaluation currently running in this
+    /// environment when cancelled.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
+
+    fn add_binding(&mut self, mut binding: Binding, evaluation: &mut Evaluation) -> EvaluationResult<()> {
         // always capture free variables from environment
         binding.value = binding.value.bind_free_from(&self.symbols);
 
         // if, after binding predefined values, the term still references
         // the name it is being bound to, reject
         if binding.value.is_free_in(&binding.identifier) {
-            println!("Error: recursive binding");
-            return Err(RecursiveBinding);
+            return Err(RecursiveBinding { identifier: binding.identifier });
         }
 
         if let BindMode::CaptureAndReduce = binding.mode {
-            binding.value = self.evaluate(binding.value)?;
+            binding.value = self.evaluate(binding.value, evaluation)?;
+            evaluation.normal_form = Some(binding.value.clone());
+        }
+
+        if binding.identifier != ANS && self.symbols.get(&binding.identifier).is_some() {
+            evaluation.diagnostics.push(Diagnostic::Redefined(binding.identifier.clone()));
+        }
+
+        let free_variables = binding.value.free_variables();
+        if !free_variables.is_empty() {
+            evaluation.diagnostics.push(Diagnostic::FreeVariables(free_variables));
         }
 
+        if binding.identifier != ANS {
+            self.names.insert(&binding.identifier, &binding.value);
+        }
         self.symbols.insert(binding);
         Ok(())
     }
 
-    fn evaluate(&self, mut term: Term) -> EvaluationResult<Term> {
+    fn notify(&mut self, event: &Event) -> Control {
+        let mut control = Control::Continue;
+        for observer in &mut self.observers {
+            if observer.notify(event) == Control::Stop {
+                control = Control::Stop;
+            }
+        }
+        control
+    }
+
+    fn evaluate(&mut self, mut term: Term, evaluation: &mut Evaluation) -> EvaluationResult<Term> {
         term = term.bind_free_from(&self.symbols);
+        if let Strategy::CallByNeed = self.strategy {
+            // the graph reaches the β-normal form, which η steps then
+            // continue from
+            term = self.evaluate_shared(&term, evaluation)?;
+        }
 
         let mut seen_terms = HashSet::new();
-        let mut reduction_count: usize = 0;
         loop {
-            if reduction_count > self.max_reductions {
-                println!("[too many reductions: {}]", reduction_count);
-                return Err(TooManyReductions);
+            let steps = evaluation.reductions + evaluation.eta_reductions;
+            if steps > self.max_reductions {
+                self.notify(&Event::LimitReached { reductions: steps, term: &term });
+                return Err(TooManyReductions {
+                    identifier: evaluation.binding.clone(),
+                    partial: term,
+                    reductions: steps,
+                });
             }
 
-            let reduct = term.reduce(Strategy::NormalOrder);
-            match reduct {
-                lambda::EvalResult::NormalForm(r) => {
-                    println!("β: {} [normal; {} reductions]", r, reduction_count);
-                    return Ok(r);
-                }
-                lambda::EvalResult::PossiblyReducible(r) => {
-                    if !seen_terms.contains(&r) {
-                        if self.echo_enabled { println!("β: {}", r); }
-                        term = r;
-                        seen_terms.insert(term.clone());
-                        reduction_count += 1;
-                    } else {
-                        println!("[non-terminating]");
-                        return Err(NonTerminating);
+            if self.cancellation.is_cancelled() {
+                return Err(Interrupted {
+                    identifier: evaluation.binding.clone(),
+                    partial: term,
+                    reductions: steps,
+                });
+            }
+
+            let (rule, r) = match term.reduce(self.strategy) {
+                lambda::EvalResult::Reduced { term: r, .. } => (Rule::Beta, r),
+                lambda::EvalResult::NormalForm(r) => match self.eta.step(r, self.strategy) {
+                    lambda::EvalResult::Reduced { term: r, .. } => (Rule::Eta, r),
+                    lambda::EvalResult::NormalForm(r) => {
+                        self.notify(&Event::NormalForm { reductions: steps, term: &r });
+                        return Ok(r);
                     }
                 }
+            };
+
+            if seen_terms.contains(&r) {
+                self.notify(&Event::CycleDetected { index: steps + 1, term: &r });
+                return Err(NonTerminating {
+                    identifier: evaluation.binding.clone(),
+                    partial: r,
+                    reductions: steps,
+                });
+            }
+
+            match rule {
+                Rule::Beta => evaluation.reductions += 1,
+                Rule::Eta => evaluation.eta_reductions += 1,
+            }
+            let control = self.notify(&Event::Step { index: steps + 1, rule, term: &r });
+
+            term = r;
+            seen_terms.insert(term.clone());
+            if control == Control::Stop {
+                return Err(Stopped {
+                    identifier: evaluation.binding.clone(),
+                    partial: term,
+                    reductions: steps + 1,
+                });
             }
         }
     }
 
-    pub fn interpret<S: AsRef<str>>(&mut self, input: S) -> EvaluationResult<()> {
-        let tokens = Token::parse_all(input.as_ref());
-        if let Err(ref e) = tokens {
-            println!("{}", e.0);
-            return Err(ParseError);
-        }
+    /// Reduces `term` to β-normal form on a graph, sharing arguments.
+    fn evaluate_shared(&mut self, term: &Term, evaluation: &mut Evaluation) -> EvaluationResult<Term> {
+        let mut graph = Graph::new(term);
 
-        let tokens = tokens.unwrap();
-        let statement = parse(&tokens);
-        match statement {
-            Err(ref e) => {
-                println!("{}", e);
-                return Ok(());
+        loop {
+            if evaluation.reductions > self.max_reductions {
+                let term = graph.to_term();
+                self.notify(&Event::LimitReached { reductions: evaluation.reductions, term: &term });
+                return Err(TooManyReductions {
+                    identifier: evaluation.binding.clone(),
+                    partial: term,
+                    reductions: evaluation.reductions,
+                });
             }
-            Ok(Statement::LetStatement(binding)) => {
-                self.add_binding(binding)?;
+
+            if self.cancellation.is_cancelled() {
+                return Err(Interrupted {
+                    identifier: evaluation.binding.clone(),
+                    partial: graph.to_term(),
+                    reductions: evaluation.reductions,
+                });
             }
-            Ok(Statement::Expression(term)) => {
-                if self.echo_enabled { println!(" : {}", term); }
-                let ans = Binding::new(Self::ANS, term, BindMode::CaptureAndReduce);
-                self.add_binding(ans)?;
+
+            if !graph.step() {
+                return Ok(graph.to_term());
+            }
+
+            evaluation.reductions += 1;
+
+            // reading the graph back unshares it, so only do it when needed
+            if !self.observers.is_empty() {
+                let r = graph.to_term();
+                let control = self.notify(&Event::Step { index: evaluation.reductions, rule: Rule::Beta, term: &r });
+                if control == Control::Stop {
+                    return Err(Stopped {
+                        identifier: evaluation.binding.clone(),
+                        partial: r,
+                        reductions: evaluation.reductions,
+                    });
+                }
             }
         }
+    }
 
-        Ok(())
+    /// Attaches an observer which is notified of every reduction step and of
+    /// how each evaluation ends. Observers are notified in the order they
+    /// were added.
+    pub fn add_observer(&mut self, observer: Box<dyn Observer>) {
+        self.observers.push(observer);
+    }
+
+    pub fn clear_observers(&mut self) {
+        self.observers.clear();
+    }
+
+    /// Parses a single statement without evaluating it.
+    pub fn parse_str(&self, input: &str) -> EvaluationResult<Statement> {
+        let tokens = Token::parse_all(input)?;
+        parse_with(&tokens, self.parse_options).map_err(|error| Self::parse_error(input, error))
+    }
+
+    fn parse_error(input: &str, error: parser::ParseError) -> EvaluationError {
+        // errors without a position are at the end of the input
+        let end = Span::new(input.len(), input.len());
+        ParseError { message: error.to_string(), span: Some(error.span().unwrap_or(end)) }
+    }
+
+    /// Parses an expression and substitutes the bindings it refers to, without
+    /// reducing it.
+    pub fn parse_expression(&self, input: &str) -> EvaluationResult<Term> {
+        match self.parse_str(input)? {
+            Statement::Expression(term) => Ok(term.bind_free_from(&self.symbols)),
+            Statement::LetStatement(_) => Err(ParseError { message: "Expected an expression".into(), span: None }),
+        }
+    }
+
+    /// Parses and evaluates a single statement, binding its result.
+    pub fn eval_str<S: AsRef<str>>(&mut self, input: S) -> EvaluationResult<Evaluation> {
+        self.cancellation.reset();
+
+        let statement = self.parse_str(input.as_ref())?;
+        let binding = match statement {
+            Statement::LetStatement(binding) => binding,
+            Statement::Expression(term) => Binding::new(ANS, term, BindMode::CaptureAndReduce),
+        };
+
+        let mut evaluation = Evaluation::new(binding.value.clone(), binding.identifier.clone());
+        self.add_binding(binding, &mut evaluation)?;
+        Ok(evaluation)
+    }
+
+    /// Parses two expressions, the first one a single operand as in
+    /// `(succ #1) (plus #1 #1)`, and decides whether they are equal, with
+    /// bindings substituted. Each side is reduced in normal order for at most
+    /// `max_reductions` steps, and η is taken into account unless `eta` is
+    /// `Eta::Off`. Like evaluation, the check can be interrupted through the
+    /// cancellation token.
+    pub fn equivalent_str(&self, input: &str) -> EvaluationResult<Equivalence> {
+        self.cancellation.reset();
+
+        let tokens = Token::parse_all(input)?;
+        let (left, right) = parse_pair_with(&tokens, self.parse_options)
+            .map_err(|error| Self::parse_error(input, error))?;
+        let (left, right) = (left.bind_free_from(&self.symbols), right.bind_free_from(&self.symbols));
+        Ok(left.equivalent(&right, self.max_reductions, self.eta != Eta::Off, &self.cancellation))
     }
 }
 
@@ -197,3 +545,242 @@ pub enum Statement {
     LetStatement(Binding),
     Expression(Term),
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ::lambda::Name;
+
+    fn parse_term(input: &str) -> Term {
+        match parser::parse(&Token::parse_all(input).unwrap()) {
+            Ok(Statement::Expression(term)) => term,
+            result => panic!("{:?}", result),
+        }
+    }
+
+    fn identity() -> Term {
+        Term::lambda(Term::variable(Name::bound(1)))
+    }
+
+    #[test]
+    fn test_eval_str_expression() {
+        let mut environment: Environment = Environment::new();
+        environment.eval_str("let I = (Lx.x)").unwrap();
+
+        let evaluation = environment.eval_str("(I I)").unwrap();
+        assert_eq!(Term::apply(Term::variable(Name::free("I".into())), Term::variable(Name::free("I".into()))),
+                   evaluation.input);
+        assert_eq!(Some(identity()), evaluation.normal_form);
+        assert_eq!(1, evaluation.reductions);
+        assert_eq!(ANS, evaluation.binding);
+        assert!(evaluation.diagnostics.is_empty());
+        assert_eq!(Some(&identity()), environment.symbol_table().get(ANS));
+    }
+
+    #[test]
+    fn test_eval_str_bindings() {
+        let mut environment: Environment = Environment::new();
+        environment.echo_enabled = false;
+
+        let evaluation = environment.eval_str("let I := ((Lx.x) (Ly.y))").unwrap();
+        assert_eq!("I", evaluation.binding);
+        assert_eq!(None, evaluation.normal_form);
+        assert_eq!(0, evaluation.reductions);
+
+        let evaluation = environment.eval_str("let I = (I a)").unwrap();
+        assert_eq!(Some(Term::variable(Name::free("a".into()))), evaluation.normal_form);
+        assert_eq!(
+            vec![Diagnostic::Redefined("I".into()), Diagnostic::FreeVariables(vec!["a".into()])],
+            evaluation.diagnostics,
+        );
+
+        let evaluation = environment.eval_str("let K = k where k = Lx y.x").unwrap();
+        assert_eq!(1, evaluation.reductions);
+        let evaluation = environment.eval_str("let x = K in x b").unwrap();
+        assert_eq!(ANS, evaluation.binding);
+        let mut symbols: Vec<_> = environment.symbol_table().symbols().cloned().collect();
+        symbols.sort();
+        assert_eq!(vec!["I".to_owned(), "K".into(), ANS.into()], symbols);
+    }
+
+    #[test]
+    fn test_name_index() {
+        let mut environment: Environment = Environment::new();
+        environment.eval_str("let F = Lx y.y").unwrap();
+        environment.eval_str("let false = F").unwrap();
+        environment.eval_str("let and = Lp q.p q p").unwrap();
+
+        let evaluation = environment.eval_str("and (Lx y.x) F").unwrap();
+        let names = environment.name_index().names(evaluation.normal_form.as_ref().unwrap());
+        assert_eq!(vec!["F", "false"], names);
+        assert!(environment.name_index().names(&identity()).contains(&"I"));
+    }
+
+    #[test]
+    fn test_decoded_results() {
+        fn decode(environment: &mut Environment, input: &str) -> String {
+            let evaluation = environment.eval_str(input).unwrap();
+            environment.decoders.decode(evaluation.normal_form.as_ref().unwrap(), &environment.printer).unwrap().to_string()
+        }
+
+        let mut environment: Environment = Environment::new();
+        environment.eval_str("let T = Lx y.x").unwrap();
+        environment.eval_str("let F = Lx y.y").unwrap();
+        environment.eval_str("let and = Lp q.p q p").unwrap();
+
+        assert_eq!("0 / false [Church numeral / boolean]", decode(&mut environment, "and T F"));
+        assert_eq!("true [boolean]", decode(&mut environment, "and T T"));
+        assert_eq!("[1, 2] [list]", decode(&mut environment, "[#1, #2]"));
+        assert_eq!("\"ab\" [string]", decode(&mut environment, "[#97, #98]"));
+    }
+
+    #[test]
+    fn test_eta() {
+        use std::sync::Mutex;
+
+        for &strategy in &[Strategy::NormalOrder, Strategy::CallByNeed] {
+            let rules = Arc::new(Mutex::new(vec![]));
+            let mut environment: Environment = Environment::new();
+            environment.strategy = strategy;
+            let observed = rules.clone();
+            environment.add_observer(Box::new(move |event: &Event| {
+                if let Event::Step { rule, .. } = *event {
+                    observed.lock().unwrap().push(rule);
+                }
+                Control::Continue
+            }));
+            let evaluation = environment.eval_str("Lx.(Ly.g y) x").unwrap();
+            assert_eq!(Some(parse_term("Lx.g x")), evaluation.normal_form);
+            assert_eq!((1, 0), (evaluation.reductions, evaluation.eta_reductions));
+
+            environment.eta = Eta::Reduce;
+            rules.lock().unwrap().clear();
+            let evaluation = environment.eval_str("Lx.(Ly.g y) x").unwrap();
+            assert_eq!(Some(parse_term("g")), evaluation.normal_form);
+            assert_eq!((1, 1), (evaluation.reductions, evaluation.eta_reductions));
+            assert_eq!(vec![Rule::Beta, Rule::Eta], *rules.lock().unwrap());
+
+            environment.eta = Eta::Expand;
+            let evaluation = environment.eval_str("(Lx.x) f").unwrap();
+            assert_eq!(Some(parse_term("Lx.f x")), evaluation.normal_form);
+            assert_eq!((1, 1), (evaluation.reductions, evaluation.eta_reductions));
+        }
+    }
+
+    #[test]
+    fn test_equivalent_str() {
+        let mut environment: Environment = Environment::new();
+        environment.eval_str("let succ = Ln f x.f (n f x)").unwrap();
+        environment.eval_str("let plus = Lm n f x.m f (n f x)").unwrap();
+
+        assert_eq!(Ok(Equivalence::Equal), environment.equivalent_str("(succ (succ #1)) plus #1 #2"));
+        for &input in &["succ (plus #2)", "#1 Lf.f"] {
+            match environment.equivalent_str(input) {
+                Ok(Equivalence::Different(_)) => {}
+                result => panic!("{:?}", result),
+            }
+        }
+        environment.eta = Eta::Reduce;
+        assert_eq!(Ok(Equivalence::Equal), environment.equivalent_str("#1 Lf.f"));
+        assert!(environment.equivalent_str("(Lx.x x").is_err());
+        assert!(environment.equivalent_str("succ").is_err());
+
+        // a cancelled check is not carried over to the next one
+        environment.cancellation_token().cancel();
+        assert_eq!(Ok(Equivalence::Equal), environment.equivalent_str("(succ #1) plus #1 #1"));
+    }
+
+    #[test]
+    fn test_eval_str_errors() {
+        let mut environment: Environment = Environment::new();
+        environment.max_reductions = 10;
+
+        assert_eq!(
+            Err(RecursiveBinding { identifier: "f".into() }),
+            environment.eval_str("let f = (Lx.(f x))"),
+        );
+        assert_eq!("recursive binding of 'f'", RecursiveBinding { identifier: "f".into() }.to_string());
+
+        let omega = Term::apply(
+            Term::lambda(Term::apply(Term::variable(Name::bound(1)), Term::variable(Name::bound(1)))),
+            Term::lambda(Term::apply(Term::variable(Name::bound(1)), Term::variable(Name::bound(1)))),
+        );
+        assert_eq!(
+            Err(NonTerminating { identifier: "w".into(), partial: omega, reductions: 1 }),
+            environment.eval_str("let w = ((Lx.(x x)) (Lx.(x x)))"),
+        );
+
+        let error = environment.eval_str("((Lx.(x x x)) (Lx.(x x x)))").unwrap_err();
+        match error {
+            TooManyReductions { ref identifier, reductions: 11, .. } if identifier == ANS => {}
+            ref e => panic!("{:?}", e),
+        }
+        assert_eq!(Some(11), error.reductions());
+        assert!(error.partial_term().is_some());
+        assert_eq!("[too many reductions: 11]", error.to_string());
+
+        assert_eq!(
+            Err(ParseError { message: "Unexpected end of input, expected `)`".into(), span: Some(Span::new(5, 5)) }),
+            environment.eval_str("(Lx.x"),
+        );
+        assert_eq!(
+            Err(ParseError { message: "Invalid token: ;".into(), span: Some(Span::new(4, 5)) }),
+            environment.eval_str("(Lx.;)"),
+        );
+    }
+
+    #[test]
+    fn test_observers() {
+        use std::sync::Mutex;
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let mut environment: Environment = Environment::new();
+        environment.max_reductions = 3;
+
+        for &name in &["first", "second"] {
+            let events = events.clone();
+            environment.add_observer(Box::new(move |event: &Event| {
+                let description = match *event {
+                    Event::Step { index, .. } => format!("{} step {}", name, index),
+                    Event::NormalForm { reductions, .. } => format!("{} normal {}", name, reductions),
+                    Event::LimitReached { reductions, .. } => format!("{} limit {}", name, reductions),
+                    Event::CycleDetected { index, .. } => format!("{} cycle {}", name, index),
+                };
+                events.lock().unwrap().push(description);
+                Control::Continue
+            }));
+        }
+
+        environment.eval_str("((Lx.x) (Ly.y))").unwrap();
+        environment.eval_str("((Lx.(x x)) (Lx.(x x)))").unwrap_err();
+        environment.eval_str("((Lx.(x x x)) (Lx.(x x x)))").unwrap_err();
+
+        assert_eq!(
+            vec![
+                "first step 1", "second step 1", "first normal 1", "second normal 1",
+                "first step 1", "second step 1", "first cycle 2", "second cycle 2",
+                "first step 1", "second step 1", "first step 2", "second step 2",
+                "first step 3", "second step 3", "first step 4", "second step 4",
+                "first limit 4", "second limit 4",
+            ],
+            *events.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_observer_stops_evaluation() {
+        for &strategy in &[Strategy::NormalOrder, Strategy::CallByNeed] {
+            let mut environment: Environment = Environment::new();
+            environment.strategy = strategy;
+            environment.add_observer(Box::new(|event: &Event| match *event {
+                Event::Step { index: 2, .. } => Control::Stop,
+                _ => Control::Continue,
+            }));
+
+            match environment.eval_str("((Lx.(x x x)) (Lx.(x x x)))") {
+                Err(Stopped { reductions: 2, .. }) => {}
+                result => panic!("{}: {:?}", strategy, result),
+            }
+        }
+    }
+}
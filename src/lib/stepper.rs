@@ -0,0 +1,155 @@
+//! Reduction of a term one step at a time, for front ends that let the user
+//! watch each redex being contracted and go back.
+
+use ::lambda::{Direction, Eta, EvalResult, Path, Strategy, Term};
+
+use std::mem;
+
+/// Steps through the reduction of a term, remembering the terms before the
+/// current one.
+#[derive(Debug, Clone)]
+pub struct Stepper {
+    strategy: Strategy,
+    eta: Eta,
+    /// The earlier terms, the first one first.
+    history: Vec<Term>,
+    term: Term,
+    /// The term after the next step and the path to the redex it contracts,
+    /// or `None` if the strategy finds no redex.
+    next: Option<(Term, Path)>,
+}
+
+impl Stepper {
+    /// Steps through the reduction of `term` under `strategy`, taking η
+    /// steps once there are no β-redexes left, as `Environment` does.
+    pub fn new(term: Term, strategy: Strategy, eta: Eta) -> Stepper {
+        let next = Stepper::next(&term, strategy, eta);
+        Stepper { strategy, eta, history: vec![], term, next }
+    }
+
+    fn next(term: &Term, strategy: Strategy, eta: Eta) -> Option<(Term, Path)> {
+        let result = match term.clone().reduce(strategy) {
+            EvalResult::NormalForm(term) => eta.step(term, strategy),
+            reduced => reduced,
+        };
+        match result {
+            EvalResult::Reduced { term, redex } => Some((term, redex)),
+            EvalResult::NormalForm(_) => None,
+        }
+    }
+
+    pub fn term(&self) -> &Term {
+        &self.term
+    }
+
+    /// The position of the redex the next step contracts, or `None` if the
+    /// current term is a normal form.
+    pub fn redex(&self) -> Option<&[Direction]> {
+        self.next.as_ref().map(|next| next.1.as_slice())
+    }
+
+    /// The number of steps leading to the current term.
+    pub fn steps(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Contracts the next redex. Returns false, doing nothing, if there is
+    /// none.
+    pub fn forward(&mut self) -> bool {
+        match self.next.take() {
+            Some((term, _)) => {
+                let next = Stepper::next(&term, self.strategy, self.eta);
+                self.history.push(mem::replace(&mut self.term, term));
+                self.next = next;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Contracts the β-redex at `path` instead of the one the strategy
+    /// picks. Returns false, doing nothing, if there is no redex at `path`.
+    pub fn contract(&mut self, path: &[Direction]) -> bool {
+        match self.term.contract_at(path) {
+            Some(term) => {
+                self.next = Stepper::next(&term, self.strategy, self.eta);
+                self.history.push(mem::replace(&mut self.term, term));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Goes back to the term before the last step. Returns false, doing
+    /// nothing, at the first term.
+    pub fn back(&mut self) -> bool {
+        match self.history.pop() {
+            Some(term) => {
+                self.next = Stepper::next(&term, self.strategy, self.eta);
+                self.term = term;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ::lambda::Direction::*;
+    use ::lexer::Token;
+    use ::parser::parse;
+    use ::runtime::Statement;
+
+    fn parse_term(input: &str) -> Term {
+        match parse(&Token::parse_all(input).unwrap()) {
+            Ok(Statement::Expression(term)) => term,
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_stepper() {
+        let mut stepper = Stepper::new(parse_term("f ((Lx.x) ((Ly.y) a))"), Strategy::NormalOrder, Eta::Off);
+        assert_eq!(Some(&[Argument][..]), stepper.redex());
+
+        assert!(stepper.forward());
+        assert_eq!(&parse_term("f ((Ly.y) a)"), stepper.term());
+        assert!(stepper.forward());
+        assert_eq!(&parse_term("f a"), stepper.term());
+        assert_eq!(None, stepper.redex());
+        assert!(!stepper.forward());
+        assert_eq!(2, stepper.steps());
+
+        assert!(stepper.back());
+        assert_eq!(&parse_term("f ((Ly.y) a)"), stepper.term());
+        assert!(stepper.back());
+        assert!(!stepper.back());
+        assert_eq!(0, stepper.steps());
+
+        let mut stepper = Stepper::new(parse_term("f ((Lx.x) ((Ly.y) a))"), Strategy::ApplicativeOrder, Eta::Off);
+        assert_eq!(Some(&[Argument, Argument][..]), stepper.redex());
+        assert!(!stepper.contract(&[Argument, Applicand]));
+        assert!(stepper.contract(&[Argument]));
+        assert_eq!(&parse_term("f ((Ly.y) a)"), stepper.term());
+        assert_eq!(Some(&[Argument][..]), stepper.redex());
+    }
+
+    #[test]
+    fn test_stepper_eta() {
+        let mut stepper = Stepper::new(parse_term("Lx.(Ly.g y) x"), Strategy::NormalOrder, Eta::Reduce);
+        assert!(stepper.forward());
+        assert_eq!(&parse_term("Lx.g x"), stepper.term());
+        assert_eq!(Some(&[][..]), stepper.redex());
+        assert!(stepper.forward());
+        assert_eq!(&parse_term("g"), stepper.term());
+        assert!(!stepper.forward());
+
+        let mut stepper = Stepper::new(parse_term("(Lx.x) f"), Strategy::NormalOrder, Eta::Expand);
+        assert!(stepper.forward());
+        assert!(stepper.forward());
+        assert_eq!(&parse_term("Lx.f x"), stepper.term());
+        assert_eq!(None, stepper.redex());
+    }
+}
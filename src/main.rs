@@ -1,21 +1,30 @@
 extern crate lambda_rust;
 extern crate rustyline;
 extern crate isatty;
+extern crate ctrlc;
 #[macro_use] extern crate lazy_static;
 
 use lambda_rust::runtime::*;
+use lambda_rust::encoding::NumeralEncoding;
+use lambda_rust::lambda::{Difference, Equivalence, Eta, Rule, Strategy, Term};
+use lambda_rust::lexer::Token;
+use lambda_rust::names::NameIndex;
+use lambda_rust::observer::{Control, Event};
+use lambda_rust::pretty::{Notation, PrettyPrinter};
+use lambda_rust::stepper::Stepper;
 use rustyline::{error::ReadlineError, config::{Config, CompletionType}};
 use isatty::*;
 use std::process;
 use std::fs::File;
 use std::io::{BufReader, BufRead};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
 
 mod commands;
 use commands::{Command, Commands, CommandCall, ArgType};
 
 mod completion;
-use completion::{Completers, completers::SymbolTableAdapter};
+use completion::{Completers, completers::{ChoiceCompleter, SymbolTableAdapter}};
 
 const QUIT: &str = "quit";
 const EXIT: &str = "exit";
@@ -24,11 +33,35 @@ const LIST: &str = "list";
 const IMPORT: &str = "import";
 const ECHO: &str = "echo";
 const REDUCTIONS: &str = "reductions";
+const STRATEGY: &str = "strategy";
+const LETTER_BINDERS: &str = "letterbinders";
+const NUMERALS: &str = "numerals";
+const WIDTH: &str = "width";
+const COLLAPSE: &str = "collapse";
+const FOLD: &str = "fold";
+const NOTATION: &str = "notation";
+const ETA: &str = "eta";
+const EQ: &str = "eq";
+const STEP: &str = "step";
+
+// evaluation recurses over terms, so give the worker more room than the
+// default thread stack
+const WORKER_STACK_SIZE: usize = 64 * 1024 * 1024;
 
 fn main() {
     let runtime: Arc<Mutex<Environment<HashSymbolTable>>> = Arc::new(Mutex::new(Environment::new()));
 
-    let completers = Completers::default().add(ArgType::Symbol, Box::new(SymbolTableAdapter::new(&runtime)));
+    // the prompt reads Ctrl-C itself, so the handler only fires while
+    // an evaluation is running and aborts just that evaluation
+    let cancellation = lock(&runtime).cancellation_token();
+    ctrlc::set_handler(move || cancellation.cancel()).expect("Error setting Ctrl-C handler");
+
+    let completers = Completers::default()
+                        .add(ArgType::Symbol, Box::new(SymbolTableAdapter::new(&runtime)))
+                        .add(ArgType::Strategy, Box::new(ChoiceCompleter::new(Strategy::ALL.iter().map(|s| s.name()))))
+                        .add(ArgType::Encoding, Box::new(ChoiceCompleter::new(NumeralEncoding::ALL.iter().map(|e| e.name()))))
+                        .add(ArgType::Notation, Box::new(ChoiceCompleter::new(Notation::ALL.iter().map(|n| n.name()))))
+                        .add(ArgType::Eta, Box::new(ChoiceCompleter::new(Eta::ALL.iter().map(|e| e.name()))));
 
     let commands = Commands::new()
                         .with_completers(completers)
@@ -38,8 +71,18 @@ fn main() {
                         .add(Command::new(SHOW, ArgType::Symbol))
                         .add(Command::nullary(LIST))
                         .add(Command::unary(IMPORT, ArgType::File))
+                        .add(Command::new(EQ, ArgType::Symbol))
+                        .add(Command::new(STEP, ArgType::Symbol))
                         .add(Command::with_arities(ECHO, ArgType::Boolean, vec![0, 1]))
                         .add(Command::with_arities(REDUCTIONS, ArgType::Number, vec![0, 1]))
+                        .add(Command::with_arities(STRATEGY, ArgType::Strategy, vec![0, 1]))
+                        .add(Command::with_arities(ETA, ArgType::Eta, vec![0, 1]))
+                        .add(Command::with_arities(LETTER_BINDERS, ArgType::Boolean, vec![0, 1]))
+                        .add(Command::with_arities(NUMERALS, ArgType::Encoding, vec![0, 1]))
+                        .add(Command::with_arities(WIDTH, ArgType::Number, vec![0, 1]))
+                        .add(Command::with_arities(COLLAPSE, ArgType::Boolean, vec![0, 1]))
+                        .add(Command::with_arities(FOLD, ArgType::Boolean, vec![0, 1]))
+                        .add(Command::with_arities(NOTATION, ArgType::Notation, vec![0, 1]))
                         .done();
 
     let mut editor = rustyline::Editor::<&Commands<Completers<_>>>::with_config(
@@ -62,21 +105,29 @@ fn main() {
             }
         };
 
-        let mut runtime_lock = runtime.lock().unwrap();
-
         let input = input.trim();
-        if input.is_empty() { continue; }
+        if is_blank(input) { continue; }
 
         if input.starts_with(commands::COMMAND_PREFIX) {
             match commands.parse(input) {
                 Err(e) => println!("{}", e),
                 Ok(c) => match c.command.name {
                     QUIT | EXIT => exit(),
-                    SHOW => show(c, &runtime_lock),
-                    LIST => list(&runtime_lock),
-                    IMPORT => import(c, &mut runtime_lock),
-                    ECHO => set_or_print_echo(c, &mut runtime_lock),
-                    REDUCTIONS => set_or_print_max_reductions(c, &mut runtime_lock),
+                    SHOW => show(c, &lock(&runtime)),
+                    LIST => list(&lock(&runtime)),
+                    IMPORT => import(c, &runtime),
+                    EQ => equivalent(c, &runtime),
+                    STEP => step(c, &mut editor, &runtime),
+                    ECHO => set_or_print_echo(c, &mut lock(&runtime)),
+                    REDUCTIONS => set_or_print_max_reductions(c, &mut lock(&runtime)),
+                    STRATEGY => set_or_print_strategy(c, &mut lock(&runtime)),
+                    ETA => set_or_print_eta(c, &mut lock(&runtime)),
+                    LETTER_BINDERS => set_or_print_letter_binders(c, &mut lock(&runtime)),
+                    NUMERALS => set_or_print_numerals(c, &mut lock(&runtime)),
+                    WIDTH => set_or_print_width(c, &mut lock(&runtime)),
+                    COLLAPSE => set_or_print_collapse(c, &mut lock(&runtime)),
+                    FOLD => set_or_print_fold(c, &mut lock(&runtime)),
+                    NOTATION => set_or_print_notation(c, &mut lock(&runtime)),
                     commands::HELP_COMMAND => {
                         let format = format::Fmt(|mut f| {
                             commands.write_help(&mut f, c.args.get(0).map(|a| *a))
@@ -90,8 +141,155 @@ fn main() {
             continue;
         }
 
-        let _ = runtime_lock.interpret(input);
+        let input = input.to_owned();
+        in_background(&runtime, move |runtime| {
+            if let Err(e) = interpret(runtime, &input) {
+                print_error(&e, &input, None, &runtime.printer);
+            }
+        });
+    }
+}
+
+/// Evaluates `input` and prints the result. Errors are left to the caller.
+fn interpret(runtime: &mut Environment, input: &str) -> EvaluationResult<()> {
+    // steps are printed as they happen, so that they are seen when the
+    // evaluation fails as well
+    runtime.clear_observers();
+    if runtime.echo_enabled {
+        if let Ok(Statement::Expression(ref term)) = runtime.parse_str(input) {
+            println!(" : {}", runtime.printer.render_at(term, 3));
+        }
+        let observer = step_printer(runtime);
+        runtime.add_observer(Box::new(observer));
     }
+
+    let evaluation = runtime.eval_str(input)?;
+    print_evaluation(&evaluation, runtime);
+    Ok(())
+}
+
+/// Prints `error`, pointing at its position in `source` if it has one, and
+/// the term an interrupted evaluation got to. The location is the file name
+/// and 1-based line number `source` starts at.
+fn print_error(error: &EvaluationError, source: &str, location: Option<(&str, usize)>, printer: &PrettyPrinter) {
+    let span = match error.span() {
+        None => {
+            let message = match location {
+                Some((file, line_number)) => format!("{}:{}: {}", file, line_number, error),
+                None if error.partial_term().is_none() => format!("Error: {}", error),
+                None => error.to_string(),
+            };
+            match *error {
+                EvaluationError::Interrupted { ref partial, .. } | EvaluationError::Stopped { ref partial, .. } => {
+                    let column = message.chars().count() + 1;
+                    println!("{} {}", message, printer.render_at(partial, column));
+                }
+                _ => println!("{}", message),
+            }
+            return;
+        }
+        Some(span) => span,
+    };
+
+    let column = span.column(source);
+    if let Some((file, line_number)) = location {
+        println!("{}:{}:{}: {}", file, line_number + span.line(source) - 1, column, error);
+    } else {
+        println!("Error: {}", error);
+    }
+    println!("  {}", span.line_text(source));
+    println!("  {}{}", " ".repeat(column - 1), "^".repeat(span.width(source).max(1)));
+}
+
+/// Whether `input` consists only of whitespace and comments.
+fn is_blank(input: &str) -> bool {
+    Token::parse_all(input).map_or(false, |tokens| tokens.is_empty())
+}
+
+/// Renders a term as a result or step, folding it if `index` is given.
+fn render(term: &Term, printer: &PrettyPrinter, index: Option<&NameIndex>) -> String {
+    match index {
+        // a term which is named as a whole is printed in full, followed by
+        // its names
+        Some(index) if index.names(term).is_empty() => printer.render_at(&index.fold(term), 3),
+        _ => printer.render_at(term, 3),
+    }
+}
+
+/// An observer printing every reduction step with the current settings.
+fn step_printer(runtime: &Environment) -> impl FnMut(&Event) -> Control + Send {
+    let printer = runtime.printer;
+    let index = if runtime.fold_names { Some(runtime.name_index().clone()) } else { None };
+    move |event: &Event| {
+        if let Event::Step { rule, term, .. } = *event {
+            println!("{}: {}", printer.notation.rule(rule), render(term, &printer, index.as_ref()));
+        }
+        Control::Continue
+    }
+}
+
+fn print_evaluation(evaluation: &Evaluation, runtime: &Environment) {
+    let printer = &runtime.printer;
+    let index = if runtime.fold_names { Some(runtime.name_index()) } else { None };
+    let show = |term: &Term| render(term, printer, index);
+
+    let (beta, eta) = (printer.notation.rule(Rule::Beta), printer.notation.rule(Rule::Eta));
+    if let Some(ref normal_form) = evaluation.normal_form {
+        if evaluation.eta_reductions > 0 {
+            println!("{}: {} [normal; {} {}, {} {} reductions]",
+                     beta, show(normal_form), evaluation.reductions, beta, evaluation.eta_reductions, eta);
+        } else {
+            println!("{}: {} [normal; {} reductions]", beta, show(normal_form), evaluation.reductions);
+        }
+        if let Some(decoded) = runtime.decoders.decode(normal_form, printer) {
+            println!("{}: {}", beta, decoded);
+        }
+        if let Some(index) = index {
+            let names = index.names(normal_form);
+            if !names.is_empty() {
+                println!("= {}", names.join(" = "));
+            }
+        }
+    }
+
+    for diagnostic in &evaluation.diagnostics {
+        println!("[{}]", diagnostic);
+    }
+}
+
+/// Runs `task` on a worker thread and waits for it to finish, keeping the
+/// REPL thread free to receive Ctrl-C.
+fn in_background<F>(runtime: &Arc<Mutex<Environment>>, task: F)
+    where F: FnOnce(&mut Environment) + Send + 'static
+{
+    let runtime = Arc::clone(runtime);
+    on_worker(move || task(&mut lock(&runtime)));
+}
+
+/// Runs `task` on a thread with `WORKER_STACK_SIZE` bytes of stack and
+/// returns its result, or `None` if it panicked.
+fn on_worker<F, T>(task: F) -> Option<T>
+    where F: FnOnce() -> T + Send + 'static, T: Send + 'static
+{
+    let worker = thread::Builder::new()
+        .stack_size(WORKER_STACK_SIZE)
+        .spawn(task)
+        .expect("Error spawning evaluation thread");
+
+    match worker.join() {
+        Ok(result) => Some(result),
+        Err(_) => {
+            println!("Error: evaluation thread panicked");
+            None
+        }
+    }
+}
+
+/// Locks the environment. A panicking evaluation leaves it poisoned, but
+/// bindings are only changed once an evaluation succeeds, so it is still
+/// usable.
+fn lock<'a>(runtime: &'a Mutex<Environment>) -> MutexGuard<'a, Environment> {
+    runtime.lock().unwrap_or_else(PoisonError::into_inner)
 }
 
 fn exit() -> ! {
@@ -123,10 +321,106 @@ fn set_or_print_max_reductions(command: CommandCall, runtime: &mut Environment)
     }
 }
 
+fn set_or_print_strategy(command: CommandCall, runtime: &mut Environment) {
+    match command.args.as_slice() {
+        [] => println!("Strategy: {}", runtime.strategy),
+        [strategy] => match strategy.parse() {
+            Ok(s) => runtime.strategy = s,
+            Err(e) => println!("Error: {}", e.0),
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn set_or_print_eta(command: CommandCall, runtime: &mut Environment) {
+    match command.args.as_slice() {
+        [] => println!("Eta: {}", runtime.eta),
+        [eta] => match eta.parse() {
+            Ok(e) => runtime.eta = e,
+            Err(e) => println!("Error: {}", e.0),
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn set_or_print_letter_binders(command: CommandCall, runtime: &mut Environment) {
+    let options = &mut runtime.parse_options;
+    match command.args.as_slice() {
+        [] => println!("Single letter binders: {}", options.single_letter_binders),
+        [boolean] => match boolean.parse() {
+            Ok(b) => options.single_letter_binders = b,
+            Err(e) => println!("Error: {}", e),
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn set_or_print_numerals(command: CommandCall, runtime: &mut Environment) {
+    let options = &mut runtime.parse_options;
+    match command.args.as_slice() {
+        [] => println!("Numeral encoding: {}", options.numerals),
+        [encoding] => match encoding.parse() {
+            Ok(e) => options.numerals = e,
+            Err(e) => println!("Error: {}", e.0),
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn set_or_print_width(command: CommandCall, runtime: &mut Environment) {
+    match command.args.as_slice() {
+        [] => println!("Line width: {}", runtime.printer.width),
+        [number] => match number.parse() {
+            Ok(u) => runtime.printer.width = u,
+            Err(e) => println!("Error: {}", e),
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn set_or_print_collapse(command: CommandCall, runtime: &mut Environment) {
+    match command.args.as_slice() {
+        [] => println!("Collapse binders: {}", runtime.printer.collapse_binders),
+        [boolean] => match boolean.parse() {
+            Ok(b) => runtime.printer.collapse_binders = b,
+            Err(e) => println!("Error: {}", e),
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn set_or_print_fold(command: CommandCall, runtime: &mut Environment) {
+    match command.args.as_slice() {
+        [] => println!("Fold into names: {}", runtime.fold_names),
+        [boolean] => match boolean.parse() {
+            Ok(b) => runtime.fold_names = b,
+            Err(e) => println!("Error: {}", e),
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn set_or_print_notation(command: CommandCall, runtime: &mut Environment) {
+    match command.args.as_slice() {
+        [] => println!("Notation: {}", runtime.printer.notation),
+        [notation] => match notation.parse() {
+            Ok(n) => runtime.printer.notation = n,
+            Err(e) => println!("Error: {}", e.0),
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Prints `name = term`, continuation lines of the term indented past the
+/// `=`.
+fn print_binding(name: &str, term: &Term, runtime: &Environment) {
+    println!("{} = {}", name, runtime.printer.render_at(term, name.chars().count() + 3));
+}
+
 fn show(command: CommandCall, runtime: &Environment) {
     for identifier in command.args {
         match runtime.symbol_table().get(identifier) {
-            Some(term) => println!("{} = {}", identifier, term),
+            Some(term) => print_binding(identifier, term, runtime),
             None => println!("Undefined identifier \"{}\"", identifier),
         }
     }
@@ -136,26 +430,168 @@ fn list(runtime: &Environment) {
     let mut bindings: Vec<_> = runtime.symbol_table().bindings().collect();
     bindings.sort_unstable_by_key(|b| b.0);
     for (name, term) in bindings {
-        println!("{} = {}", name, term);
+        print_binding(name, term, runtime);
+    }
+}
+
+/// Decides whether the two expressions given are equal and prints the answer,
+/// with the normal forms when they differ.
+fn equivalent(command: CommandCall, runtime: &Arc<Mutex<Environment>>) {
+    let input = command.args.join(" ");
+    in_background(runtime, move |runtime| {
+        for line in equivalence_report(&input, runtime) {
+            println!("{}", line);
+        }
+    });
+}
+
+/// The lines `:eq` prints for `input`, the two expressions to compare.
+fn equivalence_report(input: &str, runtime: &Environment) -> Vec<String> {
+    match runtime.equivalent_str(input) {
+        Ok(equivalence) => {
+            let mut lines = vec![equivalence.to_string()];
+            if let Equivalence::Different(Difference::NormalForms { ref left, ref right }) = equivalence {
+                lines.push(format!("  left: {}", runtime.printer.render_at(left, 8)));
+                lines.push(format!(" right: {}", runtime.printer.render_at(right, 8)));
+            }
+            lines
+        }
+        Err(e) => vec![format!("Error: {}", e)],
+    }
+}
+
+/// Lets the user reduce an expression one step at a time. Each term is shown
+/// with the redex the strategy contracts next highlighted; an empty line
+/// contracts it, `back` undoes a step, `continue` goes on to the normal form
+/// and `abort` ends the session. After `pick`, every redex is listed with a
+/// number, and entering the number contracts that redex instead. The
+/// environment is only locked while parsing, so completion keeps working at
+/// the `step>` prompt.
+fn step(command: CommandCall, editor: &mut rustyline::Editor<&Commands<Completers<ArgType>>>, runtime: &Arc<Mutex<Environment>>) {
+    const HELP: &str = "Enter: next step, <n>: contract redex n, b[ack]: previous term, \
+                        c[ontinue]: to the normal form, p[ick]: number redexes, a[bort]: stop";
+
+    let (mut stepper, max_reductions, printer, cancellation) = {
+        let runtime = lock(runtime);
+        match runtime.parse_expression(&command.args.join(" ")) {
+            Ok(term) => {
+                let stepper = Stepper::new(term, runtime.strategy, runtime.eta);
+                (stepper, runtime.max_reductions, runtime.printer, runtime.cancellation_token())
+            }
+            Err(e) => {
+                println!("Error: {}", e);
+                return;
+            }
+        }
+    };
+    // terminals show the redex in reverse video
+    let marks = if stdout_isatty() { ("\x1b[7m", "\x1b[27m") } else { ("⟦", "⟧") };
+
+    let mut pick = false;
+    loop {
+        let redexes = stepper.term().redexes();
+        // the printed term borrows `stepper`, which `continue` moves away
+        {
+            let prefix = format!("{}: ", stepper.steps());
+
+            // picking numbers every redex in place, otherwise the next one is
+            // highlighted
+            let labels: Vec<_> = (1..=redexes.len()).map(|n| format!("⟦{} ", n)).collect();
+            let marked: Vec<_> = match stepper.redex() {
+                _ if pick => redexes.iter().zip(&labels).map(|(redex, label)| (&redex[..], &label[..], "⟧")).collect(),
+                Some(redex) => vec![(redex, marks.0, marks.1)],
+                None => vec![],
+            };
+            let term = printer.render_marked(stepper.term(), prefix.chars().count(), &marked);
+
+            let next = stepper.redex().and_then(|redex| redexes.iter().position(|r| &r[..] == redex));
+            match (stepper.redex(), next) {
+                (None, _) => println!("{}{} [normal; {} reductions]", prefix, term, stepper.steps()),
+                (Some(_), Some(i)) if pick => println!("{}{} [next: {}]", prefix, term, i + 1),
+                (Some(_), _) => println!("{}{}", prefix, term),
+            }
+        }
+
+        let line = match editor.readline("step> ") {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+        match line.trim() {
+            "" if stepper.redex().is_none() => return,
+            "" => { stepper.forward(); }
+            "b" | "back" => if !stepper.back() {
+                println!("[no earlier term]");
+            }
+            "c" | "continue" => {
+                cancellation.reset();
+                let token = cancellation.clone();
+                // reduction recurses over the term, so it needs the worker's
+                // stack as much as evaluation does
+                let result = on_worker(move || {
+                    let mut steps = 0;
+                    while steps < max_reductions && !token.is_cancelled() && stepper.forward() {
+                        steps += 1;
+                    }
+                    (stepper, steps)
+                });
+                let steps = match result {
+                    Some((forwarded, steps)) => {
+                        stepper = forwarded;
+                        steps
+                    }
+                    None => return,
+                };
+                if cancellation.is_cancelled() {
+                    println!("[interrupted after {} steps]", steps);
+                } else if stepper.redex().is_some() {
+                    println!("[stopped after {} steps (limit)]", steps);
+                }
+            }
+            "p" | "pick" => pick = !pick,
+            "a" | "abort" => return,
+            input => match input.parse::<usize>() {
+                Ok(n) if n >= 1 && n <= redexes.len() => { stepper.contract(&redexes[n - 1]); }
+                Ok(_) => println!("[no redex with that number]"),
+                Err(_) => println!("{}", HELP),
+            }
+        }
     }
 }
 
-fn import(command: CommandCall, runtime: &mut Environment) {
-    let filename = command.args[0];
-    match File::open(filename) {
+fn import(command: CommandCall, runtime: &Arc<Mutex<Environment>>) {
+    let filename = command.args[0].to_owned();
+    match File::open(&filename) {
         Err(e) => println!("Error opening {}: {}", filename, e),
-        Ok(file) => {
-            let mut reader = BufReader::new(&file);
+        Ok(file) => in_background(runtime, move |runtime| {
+            let reader = BufReader::new(&file);
+            // a statement is one line, unless a block comment continues it
+            let mut statement = String::new();
+            let mut first_line = 0;
             for (line_number, line) in reader.lines().enumerate() {
-                match runtime.interpret(&line.unwrap()) {
-                    Err(_) => {
-                        println!("Error in line {}.", line_number + 1);
-                        break;
+                if statement.is_empty() {
+                    first_line = line_number + 1;
+                } else {
+                    statement.push('\n');
+                }
+                statement.push_str(&line.unwrap());
+
+                match Token::parse_all(&statement) {
+                    Err(ref e) if e.incomplete => continue,
+                    Ok(ref tokens) if tokens.is_empty() => {}
+                    _ => if let Err(e) = interpret(runtime, &statement) {
+                        print_error(&e, &statement, Some((&filename, first_line)), &runtime.printer);
+                        return;
                     }
-                    Ok(_) => continue,
                 }
+                statement.clear();
             }
-        }
+
+            if !statement.is_empty() {
+                if let Err(e) = interpret(runtime, &statement) {
+                    print_error(&e, &statement, Some((&filename, first_line)), &runtime.printer);
+                }
+            }
+        }),
     }
 }
 
@@ -169,3 +605,33 @@ mod format {
         } 
     } 
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_eq_command() {
+        let commands: Commands<Completers<_>> = Commands::new().add(Command::new(EQ, ArgType::Symbol)).done();
+        let mut runtime: Environment = Environment::new();
+        runtime.eval_str("let plus = Lm n f x.m f (n f x)").unwrap();
+        runtime.eval_str("let mult = Lm n f.m (n f)").unwrap();
+        let report = |line| equivalence_report(&commands.parse(line).unwrap().args.join(" "), &runtime);
+
+        assert_eq!(vec!["equal"], report(":eq (mult #2 #3) (plus #3 #3)"));
+        assert_eq!(vec!["equal"], report(":eq (mult #2 #3) plus #3 #3"));
+        assert_eq!(
+            vec!["different: the normal forms differ", "  left: λf.λx.f^6 x", " right: λf.λx.f^5 x"],
+            report(":eq (mult #2 #3) (plus #3 #2)"),
+        );
+        assert_eq!(vec!["Error: Trailing tokens: `)`"], report(":eq (mult #2 #3) plus #3 #3)"));
+    }
+
+    #[test]
+    fn test_panicking_evaluation() {
+        let runtime = Arc::new(Mutex::new(Environment::new()));
+        in_background(&runtime, |_| panic!("evaluation failed"));
+        in_background(&runtime, |runtime| { runtime.eval_str("let id = Lx.x").unwrap(); });
+        assert!(lock(&runtime).symbol_table().get("id").is_some());
+    }
+}